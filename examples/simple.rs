@@ -14,7 +14,7 @@ fn display(errors: &[IspellError]) {
             if !e.suggestions.is_empty() {
                 print!(" Maybe you meant '{}'?", e.suggestions[0]);
             }
-            println!("");
+            println!();
         }
     }    
 }
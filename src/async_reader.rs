@@ -6,7 +6,9 @@ use std::process::ChildStdout;
 use std::io::{BufReader, BufRead};
 use std::sync::mpsc::Sender;
 
-use error::Result;
+use crate::error::{Result, Error};
+use crate::protocol;
+use crate::transcript::{Transcript, Direction};
 
 
 /// An asynchronous reader, that reads from a spawned command stdout
@@ -14,14 +16,18 @@ use error::Result;
 pub struct AsyncReader {
     stdout: BufReader<ChildStdout>,
     sender: Sender<Result<String>>,
+    transcript: Option<Transcript>,
 }
 
 impl AsyncReader {
     /// Create a new AsyncReader
-    pub fn new(stdout: ChildStdout, sender: Sender<Result<String>>) -> AsyncReader {
+    ///
+    /// If `transcript` is set, each answer is recorded in it.
+    pub fn new(stdout: ChildStdout, sender: Sender<Result<String>>, transcript: Option<Transcript>) -> AsyncReader {
         AsyncReader {
             stdout: BufReader::new(stdout),
-            sender: sender,
+            sender,
+            transcript,
         }
     }
 
@@ -29,10 +35,14 @@ impl AsyncReader {
     pub fn read_loop(&mut self) {
         loop {
             let result = self.read();
+            let failed = result.is_err();
             match self.sender.send(result) {
                 Ok(_) => (),
                 Err(_) => break, // main process was aborted
             }
+            if failed {
+                break; // spawned process closed its stdout
+            }
         }
     }
 
@@ -40,11 +50,16 @@ impl AsyncReader {
     fn read(&mut self) -> Result<String> {
         let mut output = String::new();
         loop {
-            self.stdout.read_line(&mut output)?;
-            if output.ends_with("\n\n") || output == "\n" || output.starts_with("@") {
+            if self.stdout.read_line(&mut output)? == 0 {
+                return Err(Error::process_exited(None, String::new()));
+            }
+            if protocol::is_complete(output.as_bytes()) {
                 break;
             }
         }
+        if let Some(ref transcript) = self.transcript {
+            transcript.record(Direction::Received, output.as_bytes());
+        }
         Ok(output)
     }
 }
@@ -0,0 +1,321 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::mem;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout};
+use tokio::time;
+
+use crate::backend_info::BackendInfo;
+use crate::error::{Result, Error};
+use crate::ispell_result::{IspellResult, IspellError, RejectedWord};
+use crate::position::Unit;
+use crate::protocol;
+use crate::spell_launcher::Format;
+use crate::stderr_buffer::StderrBuffer;
+
+/// Asynchronous spell checker
+///
+/// Similar to `SpellChecker`, except it uses `tokio` to communicate with
+/// the spawned process, so it never blocks the current thread. It is
+/// created by `SpellLauncher::launch_async` and is only available with
+/// the `tokio` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// use ispell::SpellLauncher;
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// async fn main() {
+///     let mut checker = SpellLauncher::new().launch_async().await.unwrap();
+///     let errors = checker.check("This should not contain any error").await.unwrap();
+///     assert!(errors.is_empty());
+/// }
+/// ```
+pub struct AsyncSpellChecker {
+    ispell: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    /// Answer that is currently being read
+    buffer: Vec<u8>,
+    /// Number of answers that have not been read yet (e.g. because of a timeout)
+    pending: usize,
+    stderr: StderrBuffer,
+    backend_info: BackendInfo,
+    timeout: Duration,
+    unit: Unit,
+    /// Whether the terse mode is enabled
+    terse: bool,
+    /// The format set when the process was launched, if any
+    launch_format: Option<Format>,
+}
+
+impl AsyncSpellChecker {
+    /// Creates a new spell checker from a running process, that reports positions in `unit`
+    /// and was launched with `launch_format`
+    pub(crate) async fn new(mut process: Child, timeout: u64, unit: Unit, launch_format: Option<Format>)
+                            -> Result<AsyncSpellChecker> {
+        let stdin = if let Some(stdin) = process.stdin.take() {
+            stdin
+        } else {
+            return Err(Error::process("could not access stdin of spawned process"));
+        };
+
+        let stdout = if let Some(stdout) = process.stdout.take() {
+            stdout
+        } else {
+            return Err(Error::process("could not access stdout of spawned process"));
+        };
+
+        let buffer = StderrBuffer::default();
+        match process.stderr.take() {
+            Some(stderr) => {
+                let writer = buffer.clone();
+                tokio::spawn(async move {
+                    let mut stderr = BufReader::new(stderr);
+                    let mut line = vec!();
+                    while let Ok(n) = stderr.read_until(b'\n', &mut line).await {
+                        if n == 0 {
+                            break;
+                        }
+                        writer.push_bytes(&line);
+                        line.clear();
+                    }
+                    writer.close();
+                });
+            },
+            None => buffer.close(),
+        }
+
+        let mut checker = AsyncSpellChecker {
+            ispell: process,
+            stdin,
+            stdout: BufReader::new(stdout),
+            buffer: vec!(),
+            pending: 1, // the first line that displays Version
+            stderr: buffer,
+            backend_info: BackendInfo::parse(""),
+            timeout: Duration::from_millis(timeout),
+            unit,
+            terse: false,
+            launch_format,
+        };
+
+        let s = checker.read_str().await?;
+        match s.chars().next() {
+            Some('@') => {
+                checker.backend_info = BackendInfo::parse(&s);
+                Ok(checker)
+            },
+            _ => Err(Error::protocol_line("First line of ispell output doesn't start with '@', aborting",
+                                          s.lines().next().unwrap_or(""))
+                     .with_stderr(checker.stderr.text()))
+        }
+    }
+
+    /// Returns informations about the spawned program, read from its version banner
+    ///
+    /// See `SpellChecker::backend_info`.
+    pub fn backend_info(&self) -> &BackendInfo {
+        &self.backend_info
+    }
+
+    /// Returns the last lines the spawned process wrote to its stderr
+    ///
+    /// See `SpellChecker::stderr`.
+    pub fn stderr(&self) -> Vec<String> {
+        self.stderr.lines()
+    }
+
+    /// Reads the next answer from ispell, or fails if it doesn't come in time
+    async fn read_str(&mut self) -> Result<String> {
+        match time::timeout(self.timeout, self.read_answer()).await {
+            Ok(result) => {
+                let output = result?;
+                self.pending -= 1;
+                Ok(output)
+            },
+            Err(_) => Err(Error::timeout().with_stderr(self.stderr.text())),
+        }
+    }
+
+    /// Reads the next answer from ispell
+    ///
+    /// Since the content that has already been read is kept in `buffer`, this can
+    /// be cancelled by a timeout and called again later.
+    async fn read_answer(&mut self) -> Result<String> {
+        loop {
+            let n = self.stdout.read_until(b'\n', &mut self.buffer).await?;
+            if n == 0 {
+                // Give the process and the task reading stderr a little time to finish
+                let status = time::timeout(Duration::from_millis(100), self.ispell.wait()).await
+                    .ok()
+                    .and_then(|status| status.ok());
+                let stderr = &self.stderr;
+                let _ = time::timeout(Duration::from_millis(100), async {
+                    while !stderr.is_closed() {
+                        time::sleep(Duration::from_millis(10)).await;
+                    }
+                }).await;
+                return Err(Error::process_exited(status, self.stderr.text()));
+            }
+            if protocol::is_complete(&self.buffer) {
+                break;
+            }
+        }
+        let output = mem::take(&mut self.buffer);
+        Ok(String::from_utf8(output)?)
+    }
+
+    /// Write to ispell stdin
+    async fn write_str(&mut self, text: &str) -> Result<()> {
+        // First, read the answers we missed, so we are sure the next one
+        // will correspond to what we write
+        while self.pending > 0 {
+            self.read_str().await?;
+        }
+
+        // A line break would end the line early and mess up with the
+        // answers, so replace them by spaces (which keeps positions unchanged)
+        let text = text.replace(['\n', '\r'], " ");
+        self.stdin.write_all(b"^").await?;
+        self.stdin.write_all(text.as_bytes()).await?;
+        self.stdin.write_all(b"\n").await?;
+        self.stdin.flush().await?;
+        self.pending += 1;
+        Ok(())
+    }
+
+    /// Sends a command that doesn't expect an answer
+    async fn write_command(&mut self, command: &[u8], word: &str) -> Result<()> {
+        protocol::validate_word(word)?;
+        self.stdin.write_all(command).await?;
+        self.stdin.write_all(word.as_bytes()).await?;
+        self.stdin.write_all(b"\n").await?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+
+    /// Adds a word to your personal dictionary
+    ///
+    /// See `SpellChecker::add_word_to_dictionary`.
+    pub async fn add_word_to_dictionary(&mut self, word: &str) -> Result<()> {
+        if let Some(rejected) = self.add_words_to_dictionary([word]).await?.pop() {
+            return Err(Error::invalid_word(rejected.reason));
+        }
+        self.save_dictionary().await
+    }
+
+    /// Adds words to your personal dictionary, without saving it
+    ///
+    /// See `SpellChecker::add_words_to_dictionary`.
+    pub async fn add_words_to_dictionary<I, S>(&mut self, words: I) -> Result<Vec<RejectedWord>>
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str> {
+        let mut rejected = vec!();
+        for word in words {
+            let word = word.as_ref();
+            if let Err(err) = protocol::validate_word(word) {
+                rejected.push(RejectedWord {
+                    word: word.to_owned(),
+                    reason: err.to_string(),
+                });
+                continue;
+            }
+            // Make sure it is a single word before adding it (correct words are not
+            // reported in terse mode, so it is left meanwhile)
+            let terse = self.terse;
+            if terse {
+                self.set_terse(false).await?;
+            }
+            self.write_str(word).await?;
+            let answer = self.read_str().await?;
+            let mut reason = protocol::split_word(&answer, word, self.unit)?;
+            if reason.is_none() {
+                self.write_command(b"*", word).await?;
+                self.write_str(word).await?;
+                let answer = self.read_str().await?;
+                reason = protocol::rejection(&answer, word, self.unit)?;
+            }
+            if terse {
+                self.set_terse(true).await?;
+            }
+            if let Some(reason) = reason {
+                rejected.push(RejectedWord {
+                    word: word.to_owned(),
+                    reason,
+                });
+            }
+        }
+        Ok(rejected)
+    }
+
+    /// Saves your personal dictionary
+    ///
+    /// See `SpellChecker::save_dictionary`.
+    pub async fn save_dictionary(&mut self) -> Result<()> {
+        self.stdin.write_all(b"#\n").await?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+
+    /// Add a word to current session.
+    ///
+    /// See `SpellChecker::add_word`.
+    pub async fn add_word(&mut self, word: &str) -> Result<()> {
+        self.write_command(b"@", word).await
+    }
+
+    /// Changes the format of the text that is checked
+    ///
+    /// See `SpellChecker::set_format`.
+    pub async fn set_format(&mut self, format: Format) -> Result<()> {
+        let command = protocol::format_command(format, self.backend_info.flavour, self.launch_format)?;
+        self.stdin.write_all(command).await?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+
+    /// Enables or disables the terse mode
+    ///
+    /// See `SpellLauncher::terse`.
+    pub async fn set_terse(&mut self, terse: bool) -> Result<()> {
+        self.stdin.write_all(if terse { b"!\n" } else { b"%\n" }).await?;
+        self.stdin.flush().await?;
+        self.terse = terse;
+        Ok(())
+    }
+
+    /// Checks the spelling of a line.
+    ///
+    /// See `SpellChecker::check`.
+    pub async fn check(&mut self, text: &str) -> Result<Vec<IspellError>> {
+        let results = self.check_raw(text).await?;
+        let mut errors = vec!();
+
+        for elem in results.into_iter() {
+            match elem {
+                IspellResult::Miss(error)
+                    | IspellResult::Guess(error)
+                    | IspellResult::None(error)
+                    => errors.push(error),
+
+                _ => (),
+            }
+        }
+        Ok(errors)
+    }
+
+    /// Checks the spelling of a string
+    ///
+    /// See `SpellChecker::check_raw`.
+    pub async fn check_raw(&mut self, text: &str) -> Result<Vec<IspellResult>> {
+        self.write_str(text).await?;
+        let s = self.read_str().await?;
+        protocol::parse_output(&s, text, self.unit)
+    }
+}
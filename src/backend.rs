@@ -0,0 +1,186 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::backend_info::BackendInfo;
+use crate::error::{Result, Error};
+use crate::ispell_result::{IspellResult, IspellError, DocumentError, RejectedWord};
+use crate::spell_checker::{self, SpellChecker};
+
+/// A spell checker, whatever its implementation
+///
+/// It is implemented by `SpellChecker`, which talks to a spawned `ispell`, `aspell` or
+/// `hunspell` process, and by `HunspellDictionary`, which reads Hunspell dictionaries
+/// directly. Code that only needs to check words and manage the dictionary can be
+/// written once for both, and for other implementations (fakes in tests, caching
+/// layers, ...): `SpellCheckerPool` and `LanguagePools` accept any backend, and so
+/// does the `ispell-lsp` language server (with `--backend builtin`).
+///
+/// Only `check_raw`, `add_word`, `add_words_to_dictionary`, `save_dictionary` and
+/// `backend_info` must be implemented.
+///
+/// # Example
+///
+/// ```
+/// use ispell::{SpellBackend, HunspellDictionary};
+///
+/// // This also works with a `SpellChecker` returned by `SpellLauncher::launch`
+/// fn count_errors<B: SpellBackend>(checker: &mut B, text: &str) -> usize {
+///     checker.check_document(text).unwrap().len()
+/// }
+///
+/// let mut checker = HunspellDictionary::new("", "2\nhello\nworld\n").unwrap();
+/// assert_eq!(count_errors(&mut checker, "hello\nwrold"), 1);
+/// ```
+pub trait SpellBackend {
+    /// Checks the spelling of a line, returning a result for each word
+    ///
+    /// See `SpellChecker::check_raw`.
+    fn check_raw(&mut self, text: &str) -> Result<Vec<IspellResult>>;
+
+    /// Adds a word to the current session
+    ///
+    /// See `SpellChecker::add_word`.
+    fn add_word(&mut self, word: &str) -> Result<()>;
+
+    /// Adds words to the personal dictionary without saving it, returning those that
+    /// were rejected
+    ///
+    /// See `SpellChecker::add_words_to_dictionary`, which accepts any iterator over
+    /// words (this takes a slice, so the trait can be used as `dyn SpellBackend`).
+    fn add_words_to_dictionary(&mut self, words: &[&str]) -> Result<Vec<RejectedWord>>;
+
+    /// Saves the personal dictionary
+    ///
+    /// See `SpellChecker::save_dictionary`.
+    fn save_dictionary(&mut self) -> Result<()>;
+
+    /// Adds a word to the personal dictionary, and saves it
+    ///
+    /// See `SpellChecker::add_word_to_dictionary`. The default implementation calls
+    /// `add_words_to_dictionary`, then `save_dictionary`.
+    fn add_word_to_dictionary(&mut self, word: &str) -> Result<()> {
+        if let Some(rejected) = self.add_words_to_dictionary(&[word])?.pop() {
+            return Err(Error::invalid_word(rejected.reason));
+        }
+        self.save_dictionary()
+    }
+
+    /// Returns informations about the program or library that checks the spelling
+    fn backend_info(&self) -> &BackendInfo;
+
+    /// Returns true if this checker can still be used
+    ///
+    /// See `SpellChecker::is_alive`. The default implementation always returns true.
+    fn is_alive(&mut self) -> bool {
+        true
+    }
+
+    /// Checks the spelling of a line, returning only the errors
+    ///
+    /// See `SpellChecker::check`.
+    fn check(&mut self, text: &str) -> Result<Vec<IspellError>> {
+        Ok(self.check_raw(text)?
+           .into_iter()
+           .filter_map(|result| match result {
+               IspellResult::Miss(error)
+                   | IspellResult::Guess(error)
+                   | IspellResult::None(error)
+                   => Some(error),
+               _ => None,
+           })
+           .collect())
+    }
+
+    /// Checks the spelling of a whole document, line by line
+    ///
+    /// See `SpellChecker::check_document`.
+    fn check_document(&mut self, text: &str) -> Result<Vec<DocumentError>> {
+        let mut errors = vec!();
+        for (n, (start, line)) in spell_checker::lines(text).enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            for error in self.check(line)? {
+                errors.push(DocumentError::new(error, n, start));
+            }
+        }
+        Ok(errors)
+    }
+}
+
+impl SpellBackend for SpellChecker {
+    fn check_raw(&mut self, text: &str) -> Result<Vec<IspellResult>> {
+        SpellChecker::check_raw(self, text)
+    }
+
+    fn add_word(&mut self, word: &str) -> Result<()> {
+        SpellChecker::add_word(self, word)
+    }
+
+    fn add_word_to_dictionary(&mut self, word: &str) -> Result<()> {
+        SpellChecker::add_word_to_dictionary(self, word)
+    }
+
+    fn save_dictionary(&mut self) -> Result<()> {
+        SpellChecker::save_dictionary(self)
+    }
+
+    fn add_words_to_dictionary(&mut self, words: &[&str]) -> Result<Vec<RejectedWord>> {
+        SpellChecker::add_words_to_dictionary(self, words.iter())
+    }
+
+    fn backend_info(&self) -> &BackendInfo {
+        SpellChecker::backend_info(self)
+    }
+
+    fn is_alive(&mut self) -> bool {
+        SpellChecker::is_alive(self)
+    }
+
+    fn check(&mut self, text: &str) -> Result<Vec<IspellError>> {
+        SpellChecker::check(self, text)
+    }
+
+    fn check_document(&mut self, text: &str) -> Result<Vec<DocumentError>> {
+        SpellChecker::check_document(self, text)
+    }
+}
+
+impl<B: SpellBackend + ?Sized> SpellBackend for Box<B> {
+    fn check_raw(&mut self, text: &str) -> Result<Vec<IspellResult>> {
+        (**self).check_raw(text)
+    }
+
+    fn add_word(&mut self, word: &str) -> Result<()> {
+        (**self).add_word(word)
+    }
+
+    fn add_word_to_dictionary(&mut self, word: &str) -> Result<()> {
+        (**self).add_word_to_dictionary(word)
+    }
+
+    fn save_dictionary(&mut self) -> Result<()> {
+        (**self).save_dictionary()
+    }
+
+    fn add_words_to_dictionary(&mut self, words: &[&str]) -> Result<Vec<RejectedWord>> {
+        (**self).add_words_to_dictionary(words)
+    }
+
+    fn backend_info(&self) -> &BackendInfo {
+        (**self).backend_info()
+    }
+
+    fn is_alive(&mut self) -> bool {
+        (**self).is_alive()
+    }
+
+    fn check(&mut self, text: &str) -> Result<Vec<IspellError>> {
+        (**self).check(text)
+    }
+
+    fn check_document(&mut self, text: &str) -> Result<Vec<DocumentError>> {
+        (**self).check_document(text)
+    }
+}
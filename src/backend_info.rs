@@ -0,0 +1,137 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fmt;
+
+/// The program that actually runs behind the ispell protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Flavour {
+    /// International Ispell
+    Ispell,
+    /// GNU Aspell
+    Aspell,
+    /// Hunspell
+    Hunspell,
+    /// Some other ispell-compatible program
+    Unknown,
+}
+
+/// Informations about the spawned program, read from its version banner
+///
+/// When it starts, a program speaking the ispell protocol displays a line like
+/// `@(#) International Ispell Version 3.2.06 (but really Hunspell 1.7.0)`,
+/// telling which version of ispell it emulates and which program it really is.
+///
+/// # Example
+///
+/// ```no_run
+/// use ispell::{SpellLauncher, Flavour};
+/// let checker = SpellLauncher::new()
+///               .hunspell()
+///               .launch()
+///               .unwrap();
+/// let info = checker.backend_info();
+/// assert_eq!(info.flavour, Flavour::Hunspell);
+/// println!("running {}", info);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BackendInfo {
+    /// The detected program
+    pub flavour: Flavour,
+
+    /// The version of ispell that is emulated, e.g. "3.2.06"
+    pub ispell_version: Option<String>,
+
+    /// The version of the program, e.g. "1.7.0" for hunspell
+    pub version: Option<String>,
+
+    /// The full banner, without its `@(#)` prefix
+    pub banner: String,
+}
+
+impl BackendInfo {
+    /// Parses the first line displayed by ispell
+    pub(crate) fn parse(banner: &str) -> BackendInfo {
+        let banner = banner.lines()
+            .next()
+            .unwrap_or("")
+            .trim_start_matches("@(#)")
+            .trim()
+            .to_owned();
+
+        let ispell_version = banner.find("Version ")
+            .and_then(|i| banner[i + "Version ".len()..].split_whitespace().next())
+            .map(|s| s.to_owned());
+
+        let (flavour, version) = match banner.find("(but really ") {
+            Some(i) => {
+                let really = banner[i + "(but really ".len()..].trim_end_matches(')');
+                let mut words = really.split_whitespace();
+                let flavour = match words.next().map(|s| s.to_lowercase()) {
+                    Some(ref name) if name == "aspell" => Flavour::Aspell,
+                    Some(ref name) if name == "hunspell" => Flavour::Hunspell,
+                    Some(ref name) if name == "ispell" => Flavour::Ispell,
+                    _ => Flavour::Unknown,
+                };
+                (flavour, words.next().map(|s| s.trim_end_matches(')').to_owned()))
+            },
+            None if banner.contains("International Ispell") => (Flavour::Ispell, ispell_version.clone()),
+            None => (Flavour::Unknown, None),
+        };
+
+        BackendInfo {
+            flavour,
+            ispell_version,
+            version,
+            banner,
+        }
+    }
+}
+
+impl fmt::Display for Flavour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Flavour::Ispell => "Ispell",
+            Flavour::Aspell => "Aspell",
+            Flavour::Hunspell => "Hunspell",
+            Flavour::Unknown => "unknown spell checker",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for BackendInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.version {
+            Some(ref version) => write!(f, "{} {}", self.flavour, version),
+            None => write!(f, "{}", self.flavour),
+        }
+    }
+}
+
+
+#[test]
+fn parse_banners() {
+    let info = BackendInfo::parse("@(#) International Ispell Version 3.2.06 (but really Hunspell 1.7.0)\n");
+    assert_eq!(info.flavour, Flavour::Hunspell);
+    assert_eq!(info.ispell_version.as_ref().unwrap(), "3.2.06");
+    assert_eq!(info.version.as_ref().unwrap(), "1.7.0");
+    assert_eq!(&info.to_string(), "Hunspell 1.7.0");
+
+    let info = BackendInfo::parse("@(#) International Ispell Version 3.1.20 (but really Aspell 0.60.8)");
+    assert_eq!(info.flavour, Flavour::Aspell);
+    assert_eq!(info.version.as_ref().unwrap(), "0.60.8");
+
+    let info = BackendInfo::parse("@(#) International Ispell Version 3.4.00 8 Feb 2015");
+    assert_eq!(info.flavour, Flavour::Ispell);
+    assert_eq!(info.version.as_ref().unwrap(), "3.4.00");
+    assert_eq!(&info.banner, "International Ispell Version 3.4.00 8 Feb 2015");
+
+    let info = BackendInfo::parse("@(#) Some other checker");
+    assert_eq!(info.flavour, Flavour::Unknown);
+    assert!(info.version.is_none());
+}
@@ -0,0 +1,244 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, ErrorKind};
+use crate::ispell_result::{IspellResult, IspellError, DocumentError};
+use crate::position::Position;
+use crate::protocol;
+use crate::spell_checker::SpellChecker;
+
+/// Maximal number of lines that are sent to the spawned process before reading its answers
+///
+/// Bounding this keeps the memory used for pending lines and answers constant,
+/// whatever the number of lines.
+const MAX_IN_FLIGHT: usize = 64;
+
+/// Iterator over the errors of many lines, returned by `SpellChecker::check_lines`
+///
+/// Each item contains the errors of the corresponding line. Lines are sent to the
+/// spawned process ahead of time, so it doesn't wait for each answer before
+/// receiving the next line.
+///
+/// After an error, the iterator doesn't return anything else.
+pub struct CheckLines<'a, I> {
+    checker: &'a mut SpellChecker,
+    lines: I,
+    /// Lines that were sent, but whose answer has not been read yet
+    in_flight: VecDeque<String>,
+    deadline: Option<Instant>,
+    started: bool,
+    done: bool,
+}
+
+impl<'a, I, S> CheckLines<'a, I>
+    where I: Iterator<Item = S>,
+          S: AsRef<str> {
+    /// Creates a new iterator over the errors of `lines`
+    ///
+    /// If `timeout` is set, the whole batch must be checked before it expires.
+    pub(crate) fn new(checker: &'a mut SpellChecker, lines: I, timeout: Option<Duration>) -> CheckLines<'a, I> {
+        CheckLines {
+            checker,
+            lines,
+            in_flight: VecDeque::new(),
+            deadline: timeout.map(|timeout| Instant::now() + timeout),
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Sends lines until there are `MAX_IN_FLIGHT` lines waiting for an answer
+    fn fill(&mut self) -> Result<()> {
+        if !self.started {
+            self.started = true;
+            self.checker.flush_stdout()?;
+        }
+        while self.in_flight.len() < MAX_IN_FLIGHT {
+            let line = match self.lines.next() {
+                Some(line) => line,
+                None => break,
+            };
+            let text = line.as_ref().replace(['\n', '\r'], " ");
+            self.checker.send(&[b"^", text.as_bytes(), b"\n"])?;
+            self.in_flight.push_back(text);
+        }
+        Ok(())
+    }
+
+    /// Returns the next line that was checked, along with its errors
+    pub(crate) fn next_line(&mut self) -> Option<Result<(String, Vec<IspellError>)>> {
+        if self.done {
+            return None;
+        }
+        match self.read_next() {
+            Ok(Some(line)) => Some(Ok(line)),
+            Ok(None) => {
+                self.done = true;
+                None
+            },
+            Err(err) => {
+                self.done = true;
+                // An answer that can't be parsed was still read entirely, so the
+                // answers to the other lines in flight can be skipped; after other
+                // errors (e.g. a timeout), the process can't be trusted anymore
+                let in_sync = matches!(err.kind(), ErrorKind::Protocol { .. }) && self.drain().is_ok();
+                if !in_sync {
+                    self.checker.broken = true;
+                }
+                Some(Err(err))
+            },
+        }
+    }
+
+    /// Reads the answer to the oldest line in flight
+    fn read_next(&mut self) -> Result<Option<(String, Vec<IspellError>)>> {
+        self.fill()?;
+        let text = match self.in_flight.pop_front() {
+            Some(text) => text,
+            None => return Ok(None),
+        };
+        let s = self.checker.read_str_until(self.deadline)?;
+        let errors = protocol::parse_output(&s, &text, self.checker.unit())?
+            .into_iter()
+            .filter_map(|result| match result {
+                IspellResult::Miss(error)
+                    | IspellResult::Guess(error)
+                    | IspellResult::None(error) => Some(error),
+                _ => None,
+            })
+            .collect();
+        Ok(Some((text, errors)))
+    }
+}
+
+impl<'a, I, S> Iterator for CheckLines<'a, I>
+    where I: Iterator<Item = S>,
+          S: AsRef<str> {
+    type Item = Result<Vec<IspellError>>;
+
+    fn next(&mut self) -> Option<Result<Vec<IspellError>>> {
+        self.next_line().map(|result| result.map(|(_, errors)| errors))
+    }
+}
+
+impl<'a, I> CheckLines<'a, I> {
+    /// Reads the answers to the lines that are still in flight, so they are not
+    /// mistaken for answers to the next lines that will be checked
+    fn drain(&mut self) -> Result<()> {
+        while self.in_flight.pop_front().is_some() {
+            self.checker.read_str_until(None)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, I> Drop for CheckLines<'a, I> {
+    fn drop(&mut self) {
+        if !self.done && self.drain().is_err() {
+            self.checker.broken = true;
+        }
+    }
+}
+
+/// Iterator over the errors of a text read from a `BufRead`, returned by
+/// `SpellChecker::check_reader`
+///
+/// The text is read lazily, and only a bounded number of lines are kept in memory.
+/// After an error (including an I/O error reading the text), the iterator doesn't
+/// return anything else.
+pub struct CheckReader<'a, R> {
+    inner: CheckLines<'a, ReaderLines<R>>,
+    /// Errors of the last line that have not been returned yet
+    errors: VecDeque<DocumentError>,
+    line: usize,
+    line_start: Position,
+    done: bool,
+}
+
+impl<'a, R: BufRead> CheckReader<'a, R> {
+    /// Creates a new iterator over the errors of the text read from `reader`
+    pub(crate) fn new(checker: &'a mut SpellChecker, reader: R) -> CheckReader<'a, R> {
+        let lines = ReaderLines {
+            reader,
+            error: None,
+            done: false,
+        };
+        CheckReader {
+            inner: CheckLines::new(checker, lines, None),
+            errors: VecDeque::new(),
+            line: 0,
+            line_start: Position::default(),
+            done: false,
+        }
+    }
+}
+
+impl<'a, R: BufRead> Iterator for CheckReader<'a, R> {
+    type Item = Result<DocumentError>;
+
+    fn next(&mut self) -> Option<Result<DocumentError>> {
+        loop {
+            if let Some(error) = self.errors.pop_front() {
+                return Some(Ok(error));
+            }
+            if self.done {
+                return None;
+            }
+            match self.inner.next_line() {
+                Some(Ok((text, errors))) => {
+                    for error in errors {
+                        self.errors.push_back(DocumentError::new(error, self.line, self.line_start));
+                    }
+                    // Line breaks were replaced by spaces, so the length is unchanged
+                    self.line_start = self.line_start + Position::end_of(&text);
+                    self.line += 1;
+                },
+                Some(Err(err)) => {
+                    self.done = true;
+                    return Some(Err(err));
+                },
+                None => {
+                    self.done = true;
+                    return self.inner.lines.error.take().map(|err| Err(err.into()));
+                },
+            }
+        }
+    }
+}
+
+/// Iterator over the lines of a `BufRead`, including their line break
+///
+/// It stops at the first I/O error, which is kept in `error`.
+struct ReaderLines<R> {
+    reader: R,
+    error: Option<io::Error>,
+    done: bool,
+}
+
+impl<R: BufRead> Iterator for ReaderLines<R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if self.done {
+            return None;
+        }
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => {
+                self.done = true;
+                None
+            },
+            Ok(_) => Some(line),
+            Err(err) => {
+                self.done = true;
+                self.error = Some(err);
+                None
+            },
+        }
+    }
+}
@@ -0,0 +1,241 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Checks the spelling of files, using ispell, aspell or hunspell
+//!
+//! Errors are displayed as `file:line:column: word -> suggestions`, with lines
+//! and columns starting at 1. The exit code is 0 if no error was found, 1 if there
+//! were spelling errors, and 2 if something else went wrong.
+//!
+//! This binary requires the `cli` feature.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::process;
+
+use ispell::{SpellLauncher, SpellChecker, DocumentError, Format, Mode};
+use serde::Serialize;
+
+const USAGE: &str = "Usage: ispell-check [OPTIONS] FILE...
+
+Checks the spelling of each FILE (which can be a glob pattern, or - for stdin).
+
+Options:
+    --backend NAME    ispell, aspell, hunspell or auto (default: auto)
+    --dict NAME       dictionary to use (e.g. en_GB)
+    --format NAME     plain, tex, html, nroff or markdown
+    --personal FILE   personal dictionary
+    --json            display errors as JSON
+    -h, --help        display this message
+    -V, --version     display the version";
+
+/// Options read from the command line
+#[derive(Default)]
+struct Options {
+    backend: Option<Mode>,
+    dict: Option<String>,
+    format: Option<Format>,
+    personal: Option<String>,
+    json: bool,
+    files: Vec<String>,
+}
+
+/// An error found in a file, with lines and columns starting at 1
+#[derive(Serialize)]
+struct Finding {
+    file: String,
+    line: usize,
+    column: usize,
+    /// Offset in bytes from the start of the file
+    offset: usize,
+    word: String,
+    suggestions: Vec<String>,
+}
+
+impl Finding {
+    fn new(file: &str, error: DocumentError) -> Finding {
+        Finding {
+            file: file.to_owned(),
+            line: error.line + 1,
+            column: error.column.char() + 1,
+            offset: error.offset.byte(),
+            word: error.error.misspelled,
+            suggestions: error.error.suggestions,
+        }
+    }
+}
+
+fn main() {
+    process::exit(match run() {
+        Ok(true) => 1,
+        Ok(false) => 0,
+        Err(msg) => {
+            eprintln!("ispell-check: {}", msg);
+            2
+        },
+    });
+}
+
+/// Checks the files, and returns true if there was any spelling error
+fn run() -> Result<bool, String> {
+    let options = match parse_args(env::args().skip(1))? {
+        Some(options) => options,
+        None => return Ok(false),
+    };
+
+    let mut launcher = SpellLauncher::new();
+    match options.backend {
+        Some(mode) => launcher.mode(mode),
+        None => launcher.auto(),
+    };
+    if let Some(ref dict) = options.dict {
+        launcher.dictionary(dict.as_str());
+    }
+    if let Some(format) = options.format {
+        launcher.format(format);
+    }
+    if let Some(ref personal) = options.personal {
+        launcher.personal_dictionary(personal);
+    }
+    let mut checker = launcher.launch()
+        .map_err(|err| err.to_string())?;
+
+    let mut findings = vec!();
+    for file in expand(&options.files)? {
+        let errors = if file == "-" {
+            check(&mut checker, BufReader::new(io::stdin()))
+        } else {
+            let reader = File::open(&file)
+                .map_err(|err| format!("{}: {}", file, err))?;
+            check(&mut checker, BufReader::new(reader))
+        };
+        let errors = errors.map_err(|err| format!("{}: {}", file, err))?;
+        for error in errors {
+            let finding = Finding::new(&file, error);
+            if !options.json {
+                println!("{}", display(&finding));
+            }
+            findings.push(finding);
+        }
+    }
+    if options.json {
+        let json = serde_json::to_string(&findings)
+            .map_err(|err| err.to_string())?;
+        println!("{}", json);
+    }
+    Ok(!findings.is_empty())
+}
+
+/// Returns the errors of the text read from `reader`
+fn check<R: BufRead>(checker: &mut SpellChecker, reader: R) -> ispell::Result<Vec<DocumentError>> {
+    checker.check_reader(reader).collect()
+}
+
+/// Parses the arguments, or returns `None` if the program should stop (e.g. after `--help`)
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => (arg[..i].to_owned(), Some(arg[i + 1..].to_owned())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || inline.clone()
+            .or_else(|| args.next())
+            .ok_or_else(|| format!("missing value for {}", name));
+        match name.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(None);
+            },
+            "-V" | "--version" => {
+                println!("ispell-check {}", env!("CARGO_PKG_VERSION"));
+                return Ok(None);
+            },
+            "--backend" => {
+                options.backend = match value()?.as_str() {
+                    "ispell" => Some(Mode::Ispell),
+                    "aspell" => Some(Mode::Aspell),
+                    "hunspell" => Some(Mode::Hunspell),
+                    "auto" => None,
+                    other => return Err(format!("unknown backend '{}'", other)),
+                };
+            },
+            "--dict" => options.dict = Some(value()?),
+            "--format" => {
+                options.format = Some(match value()?.as_str() {
+                    "plain" => Format::Plain,
+                    "tex" => Format::Tex,
+                    "html" => Format::Html,
+                    "nroff" => Format::Nroff,
+                    "markdown" => Format::Markdown,
+                    other => return Err(format!("unknown format '{}'", other)),
+                });
+            },
+            "--personal" => options.personal = Some(value()?),
+            "--json" => options.json = true,
+            "--" => {
+                options.files.extend(args);
+                break;
+            },
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option '{}'\n\n{}", arg, USAGE));
+            },
+            _ => options.files.push(arg),
+        }
+    }
+    if options.files.is_empty() {
+        return Err(format!("no file to check\n\n{}", USAGE));
+    }
+    Ok(Some(options))
+}
+
+/// Replaces glob patterns by the files they match
+fn expand(patterns: &[String]) -> Result<Vec<String>, String> {
+    let mut files = vec!();
+    for pattern in patterns {
+        if !pattern.contains(['*', '?', '[']) {
+            files.push(pattern.clone());
+            continue;
+        }
+        let paths = glob::glob(pattern)
+            .map_err(|err| format!("invalid pattern '{}': {}", pattern, err))?;
+        let mut found = false;
+        for path in paths {
+            let path = path.map_err(|err| err.to_string())?;
+            if path.is_file() {
+                files.push(path.to_string_lossy().into_owned());
+                found = true;
+            }
+        }
+        if !found {
+            return Err(format!("no file matches '{}'", pattern));
+        }
+    }
+    Ok(files)
+}
+
+/// Formats an error as `file:line:column: word -> suggestions`
+fn display(finding: &Finding) -> String {
+    let mut s = format!("{}:{}:{}: {}", finding.file, finding.line, finding.column, finding.word);
+    if !finding.suggestions.is_empty() {
+        s.push_str(" -> ");
+        s.push_str(&finding.suggestions.join(", "));
+    }
+    s
+}
+
+
+#[test]
+fn parse_options() {
+    let args = ["--backend=aspell", "--dict", "en_GB", "--json", "a.txt", "-"];
+    let options = parse_args(args.iter().map(|s| s.to_string())).unwrap().unwrap();
+    assert_eq!(options.backend, Some(Mode::Aspell));
+    assert_eq!(options.dict.as_deref(), Some("en_GB"));
+    assert!(options.json);
+    assert_eq!(options.files, vec!("a.txt", "-"));
+
+    assert!(parse_args(vec!("--format".to_owned(), "odt".to_owned()).into_iter()).is_err());
+    assert!(parse_args(vec!("--json".to_owned()).into_iter()).is_err());
+}
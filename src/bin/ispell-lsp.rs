@@ -0,0 +1,447 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! A language server that reports spelling errors, using ispell, aspell or hunspell
+//! (or Hunspell dictionaries read without spawning a process)
+//!
+//! It talks the Language Server Protocol on stdin and stdout, publishes a diagnostic
+//! for each misspelled word of the opened documents, and offers code actions to
+//! replace a word with a suggestion, add it to the personal dictionary, or ignore it
+//! for the rest of the session. When a document changes, only the modified lines
+//! are checked again. It works with any `SpellBackend`.
+//!
+//! This binary requires the `lsp` feature.
+
+use std::collections::HashMap;
+use std::env;
+use std::process;
+
+use serde_json::json;
+use tokio::sync::Mutex;
+use tower_lsp::jsonrpc;
+use tower_lsp::lsp_types::*;
+use tower_lsp::{Client, LanguageServer, LspService, Server};
+
+use ispell::{HunspellDictionary, IspellError, Mode, SpellBackend, SpellLauncher};
+
+const USAGE: &str = "Usage: ispell-lsp [OPTIONS]
+
+Runs a language server reporting spelling errors, on stdin and stdout.
+
+Options:
+    --backend NAME    ispell, aspell, hunspell, auto (default) or builtin, which
+                      reads Hunspell dictionaries without spawning a process
+    --dict NAME       dictionary to use (e.g. en_GB)
+    --personal FILE   personal dictionary, where words are added (words can't be
+                      added to the dictionary with the builtin backend without it)
+    -h, --help        display this message
+    -V, --version     display the version";
+
+/// Command adding a word to the personal dictionary
+const ADD_TO_DICTIONARY: &str = "ispell.addToDictionary";
+
+/// Command adding a word to the current session
+const IGNORE_IN_SESSION: &str = "ispell.ignoreInSession";
+
+/// Name of the server, used as the source of diagnostics
+const SOURCE: &str = "ispell";
+
+/// Dictionary used by the builtin backend if none is given
+const DEFAULT_DICTIONARY: &str = "en_US";
+
+/// Options read from the command line
+struct Options {
+    /// Launcher of the spell checker process
+    launcher: SpellLauncher,
+    /// Dictionary read by `HunspellDictionary`, if the builtin backend is used
+    builtin: Option<String>,
+    /// Personal dictionary of the builtin backend
+    personal: Option<String>,
+}
+
+/// An opened document, along with the errors of each line
+struct Document {
+    version: Option<i32>,
+    text: String,
+    /// Errors of each line, or `None` if the line hasn't been checked since it changed
+    results: Vec<Option<Vec<IspellError>>>,
+}
+
+struct State {
+    checker: Box<dyn SpellBackend + Send>,
+    documents: HashMap<Url, Document>,
+}
+
+struct Backend {
+    client: Client,
+    state: Mutex<State>,
+    /// Whether words can be added to the personal dictionary
+    can_save: bool,
+}
+
+impl Document {
+    fn new(text: String, version: Option<i32>) -> Document {
+        let results = lines(&text).map(|_| None).collect();
+        Document {
+            version,
+            text,
+            results,
+        }
+    }
+
+    /// Applies a change sent by the client, and forgets the results of the lines it touches
+    fn apply(&mut self, change: TextDocumentContentChangeEvent) {
+        let range = match change.range {
+            Some(range) => range,
+            None => {
+                *self = Document::new(change.text, self.version);
+                return;
+            },
+        };
+        let start = offset(&self.text, range.start);
+        let end = offset(&self.text, range.end).max(start);
+        self.text.replace_range(start..end, &change.text);
+
+        let first = (range.start.line as usize).min(self.results.len().saturating_sub(1));
+        let last = (range.end.line as usize).clamp(first, self.results.len().saturating_sub(1));
+        let new_lines = change.text.matches('\n').count() + 1;
+        self.results.splice(first..=last, (0..new_lines).map(|_| None));
+    }
+
+    /// Forgets the results of the lines containing `word`
+    fn invalidate(&mut self, word: &str) {
+        for (line, result) in lines(&self.text).zip(self.results.iter_mut()) {
+            if line.contains(word) {
+                *result = None;
+            }
+        }
+    }
+
+    /// Checks the lines that changed since they were last checked
+    fn check<B: SpellBackend + ?Sized>(&mut self, checker: &mut B) -> ispell::Result<()> {
+        for (line, result) in lines(&self.text).zip(self.results.iter_mut()) {
+            if result.is_none() {
+                *result = Some(if line.trim().is_empty() {
+                    vec!()
+                } else {
+                    checker.check(line)?
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a diagnostic for each known error
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec!();
+        for (n, errors) in self.results.iter().enumerate() {
+            for error in errors.iter().flatten() {
+                let start = error.position.utf16() as u32;
+                let end = start + error.misspelled.encode_utf16().count() as u32;
+                let message = if error.suggestions.is_empty() {
+                    format!("'{}' is misspelled", error.misspelled)
+                } else {
+                    format!("'{}' is misspelled (did you mean '{}'?)", error.misspelled, error.suggestions[0])
+                };
+                diagnostics.push(Diagnostic {
+                    range: Range::new(Position::new(n as u32, start), Position::new(n as u32, end)),
+                    severity: Some(DiagnosticSeverity::INFORMATION),
+                    source: Some(SOURCE.to_owned()),
+                    message,
+                    data: Some(json!({
+                        "word": error.misspelled,
+                        "suggestions": error.suggestions,
+                    })),
+                    ..Diagnostic::default()
+                });
+            }
+        }
+        diagnostics
+    }
+}
+
+impl Backend {
+    /// Checks a document again, and publishes its diagnostics
+    async fn publish(&self, state: &mut State, uri: &Url) {
+        let State { ref mut checker, ref mut documents } = *state;
+        let document = match documents.get_mut(uri) {
+            Some(document) => document,
+            None => return,
+        };
+        // Backends are blocking, which is fine as long as other tasks can run meanwhile
+        if let Err(err) = tokio::task::block_in_place(|| document.check(&mut **checker)) {
+            self.client.log_message(MessageType::ERROR, format!("spell checking failed: {}", err)).await;
+        }
+        let diagnostics = document.diagnostics();
+        self.client.publish_diagnostics(uri.clone(), diagnostics, document.version).await;
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, _: InitializeParams) -> jsonrpc::Result<InitializeResult> {
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::INCREMENTAL)),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: vec!(ADD_TO_DICTIONARY.to_owned(), IGNORE_IN_SESSION.to_owned()),
+                    ..ExecuteCommandOptions::default()
+                }),
+                ..ServerCapabilities::default()
+            },
+            server_info: Some(ServerInfo {
+                name: "ispell-lsp".to_owned(),
+                version: Some(env!("CARGO_PKG_VERSION").to_owned()),
+            }),
+        })
+    }
+
+    async fn shutdown(&self) -> jsonrpc::Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let mut state = self.state.lock().await;
+        let document = params.text_document;
+        state.documents.insert(document.uri.clone(), Document::new(document.text, Some(document.version)));
+        self.publish(&mut state, &document.uri).await;
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        let mut state = self.state.lock().await;
+        let uri = params.text_document.uri;
+        if let Some(document) = state.documents.get_mut(&uri) {
+            document.version = Some(params.text_document.version);
+            for change in params.content_changes {
+                document.apply(change);
+            }
+        }
+        self.publish(&mut state, &uri).await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.state.lock().await.documents.remove(&uri);
+        self.client.publish_diagnostics(uri, vec!(), None).await;
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> jsonrpc::Result<Option<CodeActionResponse>> {
+        let mut actions = vec!();
+        for diagnostic in params.context.diagnostics {
+            if diagnostic.source.as_deref() != Some(SOURCE) {
+                continue;
+            }
+            let data = match diagnostic.data {
+                Some(ref data) => data,
+                None => continue,
+            };
+            let word = match data["word"].as_str() {
+                Some(word) => word.to_owned(),
+                None => continue,
+            };
+            for suggestion in data["suggestions"].as_array().into_iter().flatten().filter_map(|s| s.as_str()) {
+                let edit = TextEdit::new(diagnostic.range, suggestion.to_owned());
+                let changes = vec!((params.text_document.uri.clone(), vec!(edit))).into_iter().collect();
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("Replace with '{}'", suggestion),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec!(diagnostic.clone())),
+                    edit: Some(WorkspaceEdit::new(changes)),
+                    ..CodeAction::default()
+                }));
+            }
+            for &(command, title) in &[(ADD_TO_DICTIONARY, "Add '{}' to dictionary"),
+                                       (IGNORE_IN_SESSION, "Ignore '{}' in this session")] {
+                if command == ADD_TO_DICTIONARY && !self.can_save {
+                    continue;
+                }
+                let title = title.replace("{}", &word);
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: title.clone(),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec!(diagnostic.clone())),
+                    command: Some(Command::new(title, command.to_owned(), Some(vec!(json!(word))))),
+                    ..CodeAction::default()
+                }));
+            }
+        }
+        Ok(Some(actions))
+    }
+
+    async fn execute_command(&self, params: ExecuteCommandParams) -> jsonrpc::Result<Option<serde_json::Value>> {
+        let word = match params.arguments.first().and_then(|arg| arg.as_str()) {
+            Some(word) => word.to_owned(),
+            None => return Err(jsonrpc::Error::invalid_params("expected a word")),
+        };
+        let mut state = self.state.lock().await;
+        let checker = &mut state.checker;
+        let result = match params.command.as_str() {
+            ADD_TO_DICTIONARY if !self.can_save => {
+                return Err(jsonrpc::Error::invalid_params("no personal dictionary was set (see --personal)"));
+            },
+            ADD_TO_DICTIONARY => tokio::task::block_in_place(|| checker.add_word_to_dictionary(&word)),
+            IGNORE_IN_SESSION => checker.add_word(&word),
+            _ => return Err(jsonrpc::Error::invalid_params(format!("unknown command '{}'", params.command))),
+        };
+        if let Err(err) = result {
+            return Err(jsonrpc::Error::invalid_params(err.to_string()));
+        }
+
+        let uris: Vec<_> = state.documents.keys().cloned().collect();
+        for uri in uris {
+            if let Some(document) = state.documents.get_mut(&uri) {
+                document.invalidate(&word);
+            }
+            self.publish(&mut state, &uri).await;
+        }
+        Ok(None)
+    }
+}
+
+/// Splits a text into lines, like the Language Server Protocol does
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+}
+
+/// Returns the byte offset corresponding to a position (with a column in UTF-16 code units)
+fn offset(text: &str, position: Position) -> usize {
+    let mut start = 0;
+    for _ in 0..position.line {
+        match text[start..].find('\n') {
+            Some(i) => start += i + 1,
+            None => return text.len(),
+        }
+    }
+    let line = &text[start..];
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= position.character as usize || c == '\n' {
+            return start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+/// Parses the arguments, or returns `None` if the program should stop
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>, String> {
+    let mut launcher = SpellLauncher::new();
+    launcher.auto();
+    let mut builtin = false;
+    let mut dictionary = None;
+    let mut personal = None;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(None);
+            },
+            "-V" | "--version" => {
+                println!("ispell-lsp {}", env!("CARGO_PKG_VERSION"));
+                return Ok(None);
+            },
+            "--backend" => match value()?.as_str() {
+                "ispell" => { launcher.mode(Mode::Ispell); },
+                "aspell" => { launcher.mode(Mode::Aspell); },
+                "hunspell" => { launcher.mode(Mode::Hunspell); },
+                "auto" => { launcher.auto(); },
+                "builtin" => builtin = true,
+                other => return Err(format!("unknown backend '{}'", other)),
+            },
+            "--dict" => dictionary = Some(value()?),
+            "--personal" => personal = Some(value()?),
+            // Editors often pass this to language servers
+            "--stdio" => (),
+            _ => return Err(format!("unknown option '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+    if builtin {
+        return Ok(Some(Options {
+            launcher,
+            builtin: Some(dictionary.unwrap_or_else(|| DEFAULT_DICTIONARY.to_owned())),
+            personal,
+        }));
+    }
+    if let Some(dictionary) = dictionary {
+        launcher.dictionary(dictionary);
+    }
+    if let Some(ref personal) = personal {
+        launcher.personal_dictionary(personal);
+    }
+    Ok(Some(Options {
+        launcher,
+        builtin: None,
+        personal: None,
+    }))
+}
+
+#[tokio::main]
+async fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => return,
+        Err(msg) => {
+            eprintln!("ispell-lsp: {}", msg);
+            process::exit(2);
+        },
+    };
+    // The spawned programs have a default personal dictionary, but not the builtin backend
+    let can_save = options.builtin.is_none() || options.personal.is_some();
+    let personal = options.personal;
+    let checker: ispell::Result<Box<dyn SpellBackend + Send>> = match options.builtin {
+        Some(dictionary) => HunspellDictionary::load(&dictionary).and_then(|mut checker| {
+            if let Some(ref personal) = personal {
+                checker.set_personal_dictionary(personal)?;
+            }
+            Ok(Box::new(checker) as _)
+        }),
+        None => options.launcher.launch().map(|checker| Box::new(checker) as _),
+    };
+    let checker = match checker {
+        Ok(checker) => checker,
+        Err(err) => {
+            eprintln!("ispell-lsp: {}", err);
+            process::exit(1);
+        },
+    };
+
+    let state = Mutex::new(State {
+        checker,
+        documents: HashMap::new(),
+    });
+    let (service, socket) = LspService::new(|client| Backend { client, state, can_save });
+    Server::new(tokio::io::stdin(), tokio::io::stdout(), socket).serve(service).await;
+}
+
+
+#[test]
+fn incremental_changes() {
+    let mut document = Document::new("first line\nsécond lïne\nthird".to_owned(), None);
+    for result in &mut document.results {
+        *result = Some(vec!());
+    }
+    // Replace "lïne\nthi" by "line\nand\n"
+    document.apply(TextDocumentContentChangeEvent {
+        range: Some(Range::new(Position::new(1, 7), Position::new(2, 3))),
+        range_length: None,
+        text: "line\nand\n".to_owned(),
+    });
+    assert_eq!(&document.text, "first line\nsécond line\nand\nrd");
+    assert_eq!(document.results.len(), 4);
+    assert!(document.results[0].is_some());
+    assert!(document.results[1..].iter().all(|result| result.is_none()));
+    assert_eq!(offset("a\n😀b", Position::new(1, 2)), "a\n😀".len());
+}
+
+#[test]
+fn parse_options() {
+    let args = ["--backend", "builtin", "--personal", "words.dic"];
+    let options = parse_args(args.iter().map(|s| s.to_string())).unwrap().unwrap();
+    assert_eq!(options.builtin.as_deref(), Some(DEFAULT_DICTIONARY));
+    assert_eq!(options.personal.as_deref(), Some("words.dic"));
+    assert!(parse_args(vec!("--personal".to_owned()).into_iter()).is_err());
+}
@@ -0,0 +1,107 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! A fake spell checker speaking the `ispell -a` protocol, for tests
+//!
+//! See `MockBackend` for more details. The usual options of ispell, aspell and
+//! hunspell are accepted (so it works with the arguments added by `SpellLauncher`),
+//! but only `-p` and `--personal` have an effect.
+//!
+//! This binary requires the `mock` feature.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+
+use ispell::{MockBackend, Transcript};
+
+const USAGE: &str = "Usage: ispell-mock [OPTIONS]
+
+Speaks the ispell -a protocol on stdin and stdout, accepting every word
+except the misspelled words it is given.
+
+Options:
+    --misspelled WORD[:SUGGESTION,...]  add a misspelled word
+    --words FILE                        add misspelled words from a file
+    --banner TEXT                       first line to display
+    --latency MS                        wait before each answer
+    --crash-after N                     exit instead of giving answer N+1
+    --malformed-after N                 give malformed answers after N answers
+    -p FILE, --personal=FILE            personal dictionary
+    --replay FILE                       replay a transcript saved from a real session
+                                        (see ispell::Transcript), ignoring other options";
+
+fn main() {
+    let args: Vec<_> = env::args().skip(1).collect();
+    if let Some(i) = args.iter().position(|arg| arg == "--replay") {
+        replay(args.get(i + 1));
+    }
+    let backend = match parse_args(args.into_iter()) {
+        Ok(backend) => backend,
+        Err(msg) => {
+            eprintln!("ispell-mock: {}", msg);
+            process::exit(2);
+        },
+    };
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    if let Err(err) = backend.run(stdin.lock(), stdout.lock()) {
+        eprintln!("ispell-mock: {}", err);
+        process::exit(1);
+    }
+}
+
+/// Replays the transcript saved in `path`, then exits
+fn replay(path: Option<&String>) -> ! {
+    let result = path.ok_or_else(|| ispell::Error::new("missing value for --replay"))
+        .and_then(Transcript::load)
+        .and_then(|transcript| {
+            let stdin = io::stdin();
+            let stdout = io::stdout();
+            transcript.replay(stdin.lock(), stdout.lock())
+        });
+    match result {
+        Ok(()) => process::exit(0),
+        Err(err) => {
+            eprintln!("ispell-mock: {}", err);
+            process::exit(1);
+        },
+    }
+}
+
+/// Parses the arguments into a `MockBackend`
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<MockBackend, String> {
+    let mut backend = MockBackend::new();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        let number = |value: String| value.parse::<u64>()
+            .map_err(|_| format!("invalid number '{}'", value));
+        match arg.as_str() {
+            "--misspelled" => { backend.word_list(&value()?); },
+            "--words" => {
+                let path = value()?;
+                let list = fs::read_to_string(&path)
+                    .map_err(|err| format!("{}: {}", path, err))?;
+                backend.word_list(&list);
+            },
+            "--banner" => { backend.banner(value()?); },
+            "--latency" => { backend.latency(number(value()?)?); },
+            "--crash-after" => { backend.crash_after(number(value()?)? as usize); },
+            "--malformed-after" => { backend.malformed_after(number(value()?)? as usize); },
+            "-p" => { backend.personal_dictionary(value()?); },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            },
+            // Options of the real programs, that take a value
+            "-d" | "-i" | "-T" => { value()?; },
+            _ if arg.starts_with("--personal=") => { backend.personal_dictionary(&arg["--personal=".len()..]); },
+            // Other options of the real programs (-a, -t, --encoding=utf-8, ...)
+            _ if arg.starts_with('-') => (),
+            _ => return Err(format!("unexpected argument '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+    Ok(backend)
+}
@@ -0,0 +1,419 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use crate::error::{Result, Error};
+use crate::spell_launcher::{Mode, SpellLauncher};
+
+/// Directories where ispell hash files are usually installed
+const ISPELL_DIRS: &[&str] = &["/usr/lib/ispell", "/usr/lib64/ispell", "/usr/local/lib/ispell",
+                               "/usr/share/ispell", "/opt/local/lib/ispell"];
+
+/// Full names used by ispell dictionaries, with the corresponding language and region
+const ISPELL_NAMES: &[(&str, &str, Option<&str>)] = &[
+    ("american", "en", Some("US")),
+    ("british", "en", Some("GB")),
+    ("canadian", "en", Some("CA")),
+    ("australian", "en", Some("AU")),
+    ("english", "en", None),
+    ("francais", "fr", None),
+    ("french", "fr", None),
+    ("deutsch", "de", None),
+    ("german", "de", None),
+    ("ngerman", "de", None),
+    ("swiss", "de", Some("CH")),
+    ("espanol", "es", None),
+    ("spanish", "es", None),
+    ("italian", "it", None),
+    ("italiano", "it", None),
+    ("portugues", "pt", Some("PT")),
+    ("portuguese", "pt", Some("PT")),
+    ("brazilian", "pt", Some("BR")),
+    ("nederlands", "nl", None),
+    ("dutch", "nl", None),
+    ("svenska", "sv", None),
+    ("swedish", "sv", None),
+    ("dansk", "da", None),
+    ("danish", "da", None),
+    ("norsk", "nb", None),
+    ("nynorsk", "nn", None),
+    ("polish", "pl", None),
+    ("polski", "pl", None),
+    ("russian", "ru", None),
+    ("czech", "cs", None),
+    ("catala", "ca", None),
+    ("catalan", "ca", None),
+    ("finnish", "fi", None),
+    ("suomi", "fi", None),
+    ("irish", "ga", None),
+    ("gaeilge", "ga", None),
+    ("greek", "el", None),
+    ("hungarian", "hu", None),
+    ("magyar", "hu", None),
+    ("esperanto", "eo", None),
+];
+
+/// A dictionary that is available for a program
+///
+/// Returned by `SpellLauncher::dictionaries`.
+///
+/// # Example
+///
+/// ```no_run
+/// use ispell::SpellLauncher;
+/// let dictionaries = SpellLauncher::new()
+///                    .aspell()
+///                    .dictionaries()
+///                    .unwrap();
+/// for dict in dictionaries {
+///     println!("{} ({:?})", dict.name, dict.language_tag());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DictionaryInfo {
+    /// The name of the dictionary, as it must be passed to `SpellLauncher::dictionary`
+    pub name: String,
+
+    /// The language code, e.g. "en", if it could be guessed from the name
+    pub language: Option<String>,
+
+    /// The region code, e.g. "GB"
+    pub region: Option<String>,
+
+    /// The variant, e.g. "1901" or "ise"
+    pub variant: Option<String>,
+
+    /// The path of the dictionary files, if known
+    pub path: Option<PathBuf>,
+
+    /// The program using this dictionary
+    pub backend: Mode,
+}
+
+impl DictionaryInfo {
+    /// Creates a new `DictionaryInfo`, guessing language, region and variant from the name
+    pub(crate) fn new(name: &str, path: Option<PathBuf>, backend: Mode) -> DictionaryInfo {
+        let (language, region, variant) = match ISPELL_NAMES.iter().find(|entry| entry.0 == name) {
+            Some(&(_, language, region)) => (Some(language.to_owned()), region.map(|r| r.to_owned()), None),
+            None => parse_name(name),
+        };
+        DictionaryInfo {
+            name: name.to_owned(),
+            language,
+            region,
+            variant,
+            path,
+            backend,
+        }
+    }
+
+    /// Returns the BCP-47 language tag of this dictionary (e.g. "en-GB"), if its language is known
+    ///
+    /// The variant is only included if it is a valid BCP-47 variant (e.g. "1901").
+    pub fn language_tag(&self) -> Option<String> {
+        let mut tag = self.language.clone()?;
+        if let Some(ref region) = self.region {
+            tag.push('-');
+            tag.push_str(region);
+        }
+        if let Some(ref variant) = self.variant {
+            let bytes = variant.as_bytes();
+            let valid = variant.chars().all(|c| c.is_ascii_alphanumeric())
+                && ((5..=8).contains(&bytes.len())
+                    || (bytes.len() == 4 && bytes[0].is_ascii_digit()));
+            if valid {
+                tag.push('-');
+                tag.push_str(variant);
+            }
+        }
+        Some(tag)
+    }
+}
+
+/// Guesses language, region and variant from names such as "en_GB-ise" or "de_CH-1901"
+fn parse_name(name: &str) -> (Option<String>, Option<String>, Option<String>) {
+    let mut parts = name.splitn(2, ['_', '-']);
+    let language = parts.next().unwrap_or("");
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return (None, None, None);
+    }
+    let mut rest = parts.next().unwrap_or("");
+
+    let end = rest.find(['_', '-']).unwrap_or(rest.len());
+    let candidate = &rest[..end];
+    let is_region = (candidate.len() == 2 && candidate.chars().all(|c| c.is_ascii_alphabetic()))
+        || (candidate.len() == 3 && candidate.chars().all(|c| c.is_ascii_digit()));
+    let region = if is_region {
+        rest = rest[end..].trim_start_matches(['_', '-']);
+        Some(candidate.to_uppercase())
+    } else {
+        None
+    };
+    let variant = if rest.is_empty() {
+        None
+    } else {
+        Some(rest.to_owned())
+    };
+    (Some(language.to_lowercase()), region, variant)
+}
+
+/// A parsed BCP-47 language tag (only the parts that matter for dictionaries)
+#[derive(Debug, PartialEq)]
+struct LanguageTag {
+    language: String,
+    region: Option<String>,
+    variant: Option<String>,
+}
+
+impl LanguageTag {
+    /// Parses a tag like "en-GB", "pt-BR" or "de-CH-1901"
+    ///
+    /// Underscores are accepted too, and the script subtag (e.g. "Latn") is ignored.
+    fn parse(tag: &str) -> Option<LanguageTag> {
+        let mut subtags = tag.split(['-', '_']).peekable();
+        let language = subtags.next()?;
+        if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        if subtags.peek().map(|s| s.len() == 4 && s.chars().all(|c| c.is_ascii_alphabetic())).unwrap_or(false) {
+            subtags.next();
+        }
+        let region = match subtags.peek() {
+            Some(s) if (s.len() == 2 && s.chars().all(|c| c.is_ascii_alphabetic()))
+                || (s.len() == 3 && s.chars().all(|c| c.is_ascii_digit())) => {
+                let region = s.to_uppercase();
+                subtags.next();
+                Some(region)
+            },
+            _ => None,
+        };
+        let variants: Vec<_> = subtags.collect();
+        if variants.iter().any(|s| s.is_empty()) {
+            return None;
+        }
+        Some(LanguageTag {
+            language: language.to_lowercase(),
+            region,
+            variant: if variants.is_empty() { None } else { Some(variants.join("-")) },
+        })
+    }
+}
+
+/// Finds the dictionary that best matches a BCP-47 language tag among `dicts`
+///
+/// If there is no exact match, this falls back to a dictionary of the same region without
+/// the variant, then to one for the base language, but never to another region (e.g.
+/// `pt-BR` doesn't resolve to `pt_PT`). If `dicts` is empty (e.g. because the
+/// installed dictionaries could not be listed), the name is guessed from the usual
+/// naming scheme of `mode`.
+pub(crate) fn resolve(tag: &str, mode: Mode, dicts: &[DictionaryInfo]) -> Result<String> {
+    let parsed = LanguageTag::parse(tag)
+        .ok_or_else(|| Error::no_dictionary(tag, format!("'{}' is not a valid language tag", tag)))?;
+    let same_language = |dict: &&DictionaryInfo| dict.language.as_deref() == Some(&parsed.language);
+    let same_region = |dict: &&DictionaryInfo| dict.region == parsed.region;
+    let same_variant = |dict: &&DictionaryInfo| {
+        match (&dict.variant, &parsed.variant) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+            (a, b) => a == b,
+        }
+    };
+
+    if dicts.is_empty() {
+        return guess_name(&parsed, mode)
+            .ok_or_else(|| Error::no_dictionary(tag, format!("no known {} dictionary for language '{}'",
+                                                             mode.command_name(), tag)));
+    }
+
+    let dicts: Vec<_> = dicts.iter()
+        .filter(|dict| dict.backend == mode)
+        .collect();
+    let found = dicts.iter().find(|d| same_language(d) && same_region(d) && same_variant(d))
+        .or_else(|| dicts.iter().find(|d| same_language(d) && same_region(d) && d.variant.is_none()))
+        .or_else(|| dicts.iter().find(|d| same_language(d) && same_region(d)))
+        .or_else(|| dicts.iter().find(|d| same_language(d) && d.region.is_none() && d.variant.is_none()))
+        .or_else(|| dicts.iter().find(|d| same_language(d) && parsed.region.is_none()));
+    match found {
+        Some(dict) => Ok(dict.name.clone()),
+        None => {
+            let names: Vec<_> = dicts.iter().map(|dict| dict.name.as_str()).collect();
+            Err(Error::no_dictionary(tag, format!("no {} dictionary found for language '{}' (available: {})",
+                                                  mode.command_name(), tag, names.join(", "))))
+        },
+    }
+}
+
+/// Guesses the name of the dictionary for a language, using the usual naming scheme of `mode`
+fn guess_name(tag: &LanguageTag, mode: Mode) -> Option<String> {
+    match mode {
+        Mode::Aspell | Mode::Hunspell => match tag.region {
+            Some(ref region) => Some(format!("{}_{}", tag.language, region)),
+            None => Some(tag.language.clone()),
+        },
+        Mode::Ispell => {
+            let region = tag.region.as_deref();
+            ISPELL_NAMES.iter()
+                .find(|entry| entry.1 == tag.language && entry.2 == region)
+                .or_else(|| ISPELL_NAMES.iter().find(|entry| entry.1 == tag.language && entry.2.is_none()))
+                .or_else(|| ISPELL_NAMES.iter().find(|entry| entry.1 == tag.language && region.is_none()))
+                .map(|entry| entry.0.to_owned())
+        },
+    }
+}
+
+/// Lists the dictionaries available for `mode`, using `launcher` to run the program
+pub(crate) fn list(mode: Mode, launcher: &SpellLauncher) -> Result<Vec<DictionaryInfo>> {
+    match mode {
+        Mode::Aspell => {
+            let (output, _) = run(launcher, &["dump", "dicts"])?;
+            let dir = run(launcher, &["config", "dict-dir"])
+                .map(|(s, _)| PathBuf::from(s.trim()))
+                .ok();
+            Ok(parse_aspell(&output, dir.as_deref()))
+        },
+        Mode::Hunspell => {
+            // Depending on the version, the list is printed on stdout or stderr
+            let (stdout, stderr) = run(launcher, &["-D"])?;
+            Ok(parse_hunspell(&(stdout + &stderr)))
+        },
+        Mode::Ispell => {
+            let mut dicts = vec!();
+            for dir in ISPELL_DIRS {
+                dicts.extend(scan_ispell_dir(Path::new(dir)));
+            }
+            Ok(dicts)
+        },
+    }
+}
+
+/// Runs the program of `launcher` with `args`, and returns its stdout and stderr
+fn run(launcher: &SpellLauncher, args: &[&str]) -> Result<(String, String)> {
+    let output = launcher.new_command()
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| Error::spawn_failed(launcher.command_name(), err))?;
+    Ok((String::from_utf8(output.stdout)?, String::from_utf8_lossy(&output.stderr).into_owned()))
+}
+
+/// Parses the output of `aspell dump dicts`
+fn parse_aspell(output: &str, dir: Option<&Path>) -> Vec<DictionaryInfo> {
+    output.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|name| {
+            let path = dir.map(|dir| dir.join(format!("{}.multi", name)))
+                .filter(|path| path.exists());
+            DictionaryInfo::new(name, path, Mode::Aspell)
+        })
+        .collect()
+}
+
+/// Parses the output of `hunspell -D`
+///
+/// The available dictionaries are listed, one path per line, after the
+/// "AVAILABLE DICTIONARIES" line.
+fn parse_hunspell(output: &str) -> Vec<DictionaryInfo> {
+    let mut dicts: Vec<DictionaryInfo> = vec!();
+    let mut in_list = false;
+    for line in output.lines() {
+        if line.starts_with("AVAILABLE DICTIONARIES") {
+            in_list = true;
+        } else if in_list {
+            let line = line.trim();
+            if line.is_empty() || line.ends_with(':') {
+                break;
+            }
+            let path = PathBuf::from(line);
+            let name = match path.file_name() {
+                Some(name) => name.to_string_lossy().into_owned(),
+                None => continue,
+            };
+            // The same dictionary can be found in several directories
+            if !dicts.iter().any(|dict| dict.name == name) {
+                dicts.push(DictionaryInfo::new(&name, Some(path), Mode::Hunspell));
+            }
+        }
+    }
+    dicts
+}
+
+/// Lists the ispell hash files in `dir`
+fn scan_ispell_dir(dir: &Path) -> Vec<DictionaryInfo> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec!(),
+    };
+    let mut dicts: Vec<_> = entries.filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map(|ext| ext == "hash").unwrap_or(false))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            Some(DictionaryInfo::new(&name, Some(path.clone()), Mode::Ispell))
+        })
+        .collect();
+    dicts.sort_by(|a, b| a.name.cmp(&b.name));
+    dicts
+}
+
+
+#[test]
+fn dictionary_names() {
+    let dict = DictionaryInfo::new("en_GB-ise-w_accents", None, Mode::Aspell);
+    assert_eq!(dict.language.as_deref(), Some("en"));
+    assert_eq!(dict.region.as_deref(), Some("GB"));
+    assert_eq!(dict.variant.as_deref(), Some("ise-w_accents"));
+    assert_eq!(dict.language_tag().as_deref(), Some("en-GB"));
+
+    let dict = DictionaryInfo::new("de_CH-1901", None, Mode::Hunspell);
+    assert_eq!(dict.language_tag().as_deref(), Some("de-CH-1901"));
+
+    let dict = DictionaryInfo::new("british", None, Mode::Ispell);
+    assert_eq!(dict.language_tag().as_deref(), Some("en-GB"));
+
+    let dict = DictionaryInfo::new("es_419", None, Mode::Hunspell);
+    assert_eq!(dict.region.as_deref(), Some("419"));
+
+    let dict = DictionaryInfo::new("klingon", None, Mode::Ispell);
+    assert!(dict.language.is_none());
+}
+
+#[test]
+fn parse_outputs() {
+    let dicts = parse_aspell("en\nen_US\nfr\n", None);
+    let names: Vec<_> = dicts.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, vec!("en", "en_US", "fr"));
+
+    let output = "SEARCH PATH:\n.:/usr/share/hunspell\n\
+                  AVAILABLE DICTIONARIES (path is not mandatory for -d option):\n\
+                  /usr/share/hunspell/en_US\n/usr/share/hunspell/fr_FR\n/usr/share/myspell/en_US\n\
+                  LOADED DICTIONARY:\n/usr/share/hunspell/en_US.aff\n/usr/share/hunspell/en_US.dic\n";
+    let dicts = parse_hunspell(output);
+    assert_eq!(dicts.len(), 2);
+    assert_eq!(&dicts[1].name, "fr_FR");
+    assert_eq!(dicts[1].path.as_ref().unwrap(), Path::new("/usr/share/hunspell/fr_FR"));
+}
+
+#[test]
+fn resolve_tags() {
+    let dicts: Vec<_> = ["en_GB", "en_US", "de_CH", "de_CH-1901", "de_DE", "pt_PT", "fr"].iter()
+        .map(|name| DictionaryInfo::new(name, None, Mode::Hunspell))
+        .collect();
+    assert_eq!(resolve("en-GB", Mode::Hunspell, &dicts).unwrap(), "en_GB");
+    assert_eq!(resolve("de-CH-1901", Mode::Hunspell, &dicts).unwrap(), "de_CH-1901");
+    assert_eq!(resolve("de-CH", Mode::Hunspell, &dicts).unwrap(), "de_CH");
+    assert_eq!(resolve("fr-CA", Mode::Hunspell, &dicts).unwrap(), "fr");
+    assert_eq!(resolve("pt", Mode::Hunspell, &dicts).unwrap(), "pt_PT");
+    let err = resolve("pt-BR", Mode::Hunspell, &dicts).unwrap_err();
+    assert!(matches!(err.kind(), crate::ErrorKind::NoDictionary { .. }), "{}", err);
+    assert!(resolve("it", Mode::Hunspell, &dicts).is_err());
+    assert!(resolve("not a tag", Mode::Hunspell, &dicts).is_err());
+
+    assert_eq!(resolve("en-GB", Mode::Ispell, &[]).unwrap(), "british");
+    assert_eq!(resolve("pt-BR", Mode::Ispell, &[]).unwrap(), "brazilian");
+    assert_eq!(resolve("sr-Latn-RS", Mode::Aspell, &[]).unwrap(), "sr_RS");
+}
@@ -7,81 +7,283 @@ use std::result;
 use std::fmt;
 use std::string::FromUtf8Error;
 use std::io;
+use std::process::ExitStatus;
 
-/// Internal ErrorType
-#[derive(Debug, PartialEq)]
-enum ErrorType {
-    /// Default
-    Default,
+use crate::spell_launcher::Mode;
+
+/// The kind of an `Error`
+///
+/// # Example
+///
+/// ```
+/// use ispell::{SpellLauncher, ErrorKind};
+/// let err = SpellLauncher::new()
+///           .command("not-a-spell-checker")
+///           .launch()
+///           .err()
+///           .unwrap();
+/// match err.kind() {
+///     ErrorKind::SpawnFailed { command } => assert_eq!(command, "not-a-spell-checker"),
+///     _ => panic!("unexpected error: {}", err),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The spawned process didn't answer in time (see `SpellLauncher::timeout`)
+    Timeout,
+
+    /// The command could not be spawned (e.g. it isn't installed)
+    SpawnFailed {
+        /// Name of the command
+        command: String,
+    },
+
+    /// The spawned process closed its output, usually because it exited
+    ProcessExited {
+        /// The exit status of the process, if it had already exited
+        status: Option<ExitStatus>,
+        /// What the process wrote to its standard error, if it was captured
+        stderr: String,
+    },
+
+    /// The output of the spawned process could not be understood
+    Protocol {
+        /// The offending line
+        line: String,
+    },
+
+    /// A word contains illegal characters (e.g. spaces)
+    InvalidWord,
+
+    /// The spawned process could not be used (e.g. its input or output could not be accessed)
     Process,
+
+    /// Error reading from or writing to the spawned process
+    Io,
+
+    /// The output of the spawned process isn't valid UTF-8
     Utf8,
-    Protocol,
-    InvalidWord,
+
+    /// The spawned process crashed or timed out, and restarting it didn't help
+    /// (see `SpellLauncher::auto_restart`)
+    Restart {
+        /// Number of restarts before giving up
+        restarts: u32,
+    },
+
+    /// None of the programs tried by `SpellLauncher::auto` could be launched
+    NoBackend {
+        /// Each program that was tried, with the reason why it failed
+        failures: Vec<(Mode, String)>,
+    },
+
+    /// No dictionary matches the language requested with `SpellLauncher::language`
+    NoDictionary {
+        /// The requested language tag
+        language: String,
+    },
+
+    /// The spawned program doesn't support this feature
+    Unsupported,
+
+    /// Any other error
+    Other,
 }
 
 /// Result type (returned by most methods of this library)
 pub type Result<T> = result::Result<T, Error>;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 /// Error type returned by methods of this library
 pub struct Error {
     msg: String,
-    variant: ErrorType
+    kind: ErrorKind,
+    stderr: String,
+    restarts: u32,
+    source: Option<Box<dyn error::Error + Send + Sync>>,
 }
 
 impl Error {
-    /// Creates a new default error
-    pub fn new<S: Into<String>>(msg: S) -> Error {
+    /// Creates a new error of the given kind
+    fn with_kind<S: Into<String>>(msg: S, kind: ErrorKind) -> Error {
         Error {
             msg: msg.into(),
-            variant: ErrorType::Default,
+            kind,
+            stderr: String::new(),
+            restarts: 0,
+            source: None,
         }
     }
 
+    /// Creates a new default error
+    pub fn new<S: Into<String>>(msg: S) -> Error {
+        Error::with_kind(msg, ErrorKind::Other)
+    }
+
     /// Create a new process error
     ///
     /// (for errors launching Ispell)
     pub fn process<S: Into<String>>(msg: S) -> Error {
-        Error {
-            msg: msg.into(),
-            variant: ErrorType::Process
-        }
+        Error::with_kind(msg, ErrorKind::Process)
     }
 
     /// Create a new UTF8 error
     ///
     /// (for errors converting to UTF8)
     pub fn utf8<S: Into<String>>(msg: S) -> Error {
-        Error {
-            msg: msg.into(),
-            variant: ErrorType::Utf8,
-        }
+        Error::with_kind(msg, ErrorKind::Utf8)
     }
 
     /// Creates a new protocol error
     ///
     /// (when we didn't understand ispell output)
     pub fn protocol<S: Into<String>>(msg: S) -> Error {
-        Error {
-            msg: msg.into(),
-            variant: ErrorType::Protocol,
-        }
+        Error::protocol_line(msg, "")
+    }
+
+    /// Creates a new protocol error, keeping the offending line
+    ///
+    /// (when we didn't understand the `line` of ispell output)
+    pub fn protocol_line<S: Into<String>>(msg: S, line: &str) -> Error {
+        Error::with_kind(msg, ErrorKind::Protocol { line: line.to_owned() })
     }
 
     /// Creates a new invalid word error
     ///
     /// (when the user uses illegal characters in a word)
     pub fn invalid_word<S: Into<String>>(msg: S) -> Error {
-        Error {
-            msg: msg.into(),
-            variant: ErrorType::InvalidWord,
+        Error::with_kind(msg, ErrorKind::InvalidWord)
+    }
+
+    /// Creates a new timeout error
+    ///
+    /// (when the spawned process didn't answer in time)
+    pub fn timeout() -> Error {
+        Error::with_kind("timeout error: spawned process didn't respond in time, aborting",
+                         ErrorKind::Timeout)
+    }
+
+    /// Creates a new spawn error
+    ///
+    /// (when `command` could not be spawned)
+    pub fn spawn_failed(command: &str, err: io::Error) -> Error {
+        let mut error = Error::with_kind(format!("could not successfully spawn process '{}': {}", command, err),
+                                         ErrorKind::SpawnFailed { command: command.to_owned() });
+        error.source = Some(Box::new(err));
+        error
+    }
+
+    /// Creates a new process exited error
+    ///
+    /// (when the spawned process closed its output)
+    pub fn process_exited(status: Option<ExitStatus>, stderr: String) -> Error {
+        let mut msg = match status {
+            Some(status) => format!("spawned process exited early ({}), aborting", status),
+            None => String::from("spawned process closed its stdout early, aborting"),
+        };
+        if !stderr.is_empty() {
+            msg.push_str(&format!(": {}", stderr.trim_end()));
         }
+        let mut error = Error::with_kind(msg, ErrorKind::ProcessExited { status, stderr: stderr.clone() });
+        error.stderr = stderr;
+        error
+    }
+
+    /// Creates a new restart error
+    ///
+    /// (when the spawned process crashed or timed out, and was restarted `restarts`
+    /// times without success; `err` is the last error)
+    pub fn restart(err: Error, restarts: u32) -> Error {
+        let mut error = Error::with_kind(format!("{} (gave up after {} restart(s))", err, restarts),
+                                         ErrorKind::Restart { restarts });
+        error.stderr = err.stderr.clone();
+        error.restarts = restarts;
+        error.source = Some(Box::new(err));
+        error
+    }
+
+    /// Creates a new error telling that no program could be launched
+    ///
+    /// (when `SpellLauncher::auto` failed; `failures` tells why each program failed)
+    pub fn no_backend(failures: Vec<(Mode, String)>) -> Error {
+        let reasons: Vec<_> = failures.iter()
+            .map(|(mode, reason)| format!("{}: {}", mode.command_name(), reason))
+            .collect();
+        Error::with_kind(format!("could not launch any spell checker ({})", reasons.join("; ")),
+                         ErrorKind::NoBackend { failures })
+    }
+
+    /// Creates a new error telling that no dictionary matches a language
+    ///
+    /// (when `SpellLauncher::language` couldn't find a dictionary for `language`)
+    pub fn no_dictionary<S: Into<String>>(language: &str, msg: S) -> Error {
+        Error::with_kind(msg, ErrorKind::NoDictionary { language: language.to_owned() })
+    }
+
+    /// Creates a new unsupported error
+    ///
+    /// (when the spawned program doesn't support what was requested, e.g. a format)
+    pub fn unsupported<S: Into<String>>(msg: S) -> Error {
+        Error::with_kind(msg, ErrorKind::Unsupported)
+    }
+
+    /// Attaches what the spawned process wrote to its stderr
+    pub(crate) fn with_stderr(mut self, stderr: String) -> Error {
+        if !stderr.is_empty() && self.stderr.is_empty() {
+            self.msg.push_str(&format!(" (stderr: {})", stderr.trim_end()));
+            self.stderr = stderr;
+        }
+        self
+    }
+
+    /// Tells that the spawned process had been restarted `restarts` times before this
+    /// error occurred
+    pub(crate) fn with_restarts(mut self, restarts: u32) -> Error {
+        if restarts > 0 && self.restarts == 0 {
+            self.msg.push_str(&format!(" (after {} restart(s))", restarts));
+            self.restarts = restarts;
+        }
+        self
+    }
+
+    /// Returns the kind of this error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Returns what the spawned process wrote to its stderr before this error occurred
+    ///
+    /// This is empty if the error doesn't come from the spawned process, or if the
+    /// process didn't write anything.
+    pub fn stderr(&self) -> &str {
+        &self.stderr
+    }
+
+    /// Returns the number of times the spawned process was restarted before this error
+    ///
+    /// This is always zero, unless automatic restarts were enabled with
+    /// `SpellLauncher::auto_restart`. The kind of the error is `ErrorKind::Restart` if
+    /// the restarts didn't help; otherwise the process was restarted successfully,
+    /// but the call failed for another reason.
+    pub fn restarts(&self) -> u32 {
+        self.restarts
     }
 }
 
 impl error::Error for Error {
-    fn description(&self) -> &str {
-        &self.msg
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self.source {
+            Some(ref source) => Some(&**source),
+            None => None,
+        }
+    }
+}
+
+/// Errors are equal if they have the same kind and message
+impl PartialEq for Error {
+    fn eq(&self, other: &Error) -> bool {
+        self.kind == other.kind && self.msg == other.msg
     }
 }
 
@@ -93,12 +295,17 @@ impl fmt::Display for Error {
 
 impl From<FromUtf8Error> for Error {
     fn from(err: FromUtf8Error) -> Error {
-        Error::utf8(format!("error decoding ispell output to utf8: {}", err))
+        let mut error = Error::utf8(format!("error decoding ispell output to utf8: {}", err));
+        error.source = Some(Box::new(err));
+        error
     }
 }
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Error {
-        Error::process(format!("error while reading/writing to ispell: {}", err))
+        let mut error = Error::with_kind(format!("error while reading/writing to ispell: {}", err),
+                                         ErrorKind::Io);
+        error.source = Some(Box::new(err));
+        error
     }
 }
@@ -0,0 +1,367 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Parsing of Hunspell `.aff` and `.dic` files
+
+use std::collections::HashMap;
+
+use crate::error::{Result, Error};
+
+/// A flag, whatever its syntax in the `.aff` file
+pub(crate) type Flag = u32;
+
+/// How flags are written (the `FLAG` option)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FlagType {
+    /// One character (the default)
+    Char,
+    /// Two characters
+    Long,
+    /// Numbers separated by commas
+    Num,
+}
+
+/// A part of the condition of an affix
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Any,
+    Char(char),
+    Set(Vec<char>),
+    NotSet(Vec<char>),
+}
+
+impl Condition {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            Condition::Any => true,
+            Condition::Char(expected) => c == expected,
+            Condition::Set(ref set) => set.contains(&c),
+            Condition::NotSet(ref set) => !set.contains(&c),
+        }
+    }
+}
+
+/// A prefix or suffix rule
+#[derive(Debug, Clone)]
+pub(crate) struct Affix {
+    pub flag: Flag,
+    /// Whether it can be combined with an affix of the other kind
+    pub cross: bool,
+    /// What is removed from the root
+    pub strip: String,
+    /// What is added to the root
+    pub add: String,
+    condition: Vec<Condition>,
+    /// Flags of the affix itself (e.g. for twofold suffixes)
+    pub flags: Vec<Flag>,
+}
+
+impl Affix {
+    /// Returns the root of `word` if it was formed with this prefix
+    pub fn strip_prefix(&self, word: &str) -> Option<String> {
+        let rest = word.strip_prefix(self.add.as_str())?;
+        if rest.is_empty() {
+            return None;
+        }
+        let root = format!("{}{}", self.strip, rest);
+        let mut chars = root.chars();
+        if self.condition.iter().all(|condition| chars.next().map(|c| condition.matches(c)).unwrap_or(false)) {
+            Some(root)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the root of `word` if it was formed with this suffix
+    pub fn strip_suffix(&self, word: &str) -> Option<String> {
+        let rest = word.strip_suffix(self.add.as_str())?;
+        if rest.is_empty() {
+            return None;
+        }
+        let root = format!("{}{}", rest, self.strip);
+        let mut chars = root.chars().rev();
+        if self.condition.iter().rev().all(|condition| chars.next().map(|c| condition.matches(c)).unwrap_or(false)) {
+            Some(root)
+        } else {
+            None
+        }
+    }
+}
+
+/// The content of an `.aff` file
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AffixFile {
+    flag_type: Option<FlagType>,
+    /// Flag aliases (`AF`), that can replace flags in the `.dic` file
+    aliases: Vec<Vec<Flag>>,
+    pub prefixes: Vec<Affix>,
+    pub suffixes: Vec<Affix>,
+    /// Characters to try when looking for suggestions
+    pub try_chars: Vec<char>,
+    /// Groups of neighbour keys
+    pub keys: Vec<Vec<char>>,
+    /// Common replacements, for suggestions
+    pub replacements: Vec<(String, String)>,
+    /// Groups of related characters, for suggestions
+    pub map: Vec<Vec<String>>,
+    /// Characters that are part of words, in addition to letters
+    pub word_chars: Vec<char>,
+    pub forbidden: Option<Flag>,
+    pub no_suggest: Option<Flag>,
+    pub need_affix: Option<Flag>,
+    pub keep_case: Option<Flag>,
+    pub only_in_compound: Option<Flag>,
+    pub compound: Option<Flag>,
+    pub compound_begin: Option<Flag>,
+    pub compound_middle: Option<Flag>,
+    pub compound_end: Option<Flag>,
+    pub compound_min: usize,
+    pub compound_max: Option<usize>,
+}
+
+impl AffixFile {
+    /// Parses an `.aff` file
+    pub fn parse(text: &str) -> Result<AffixFile> {
+        let mut aff = AffixFile {
+            compound_min: 3,
+            ..AffixFile::default()
+        };
+        let mut lines = text.lines()
+            .map(|line| line.trim_start_matches('\u{feff}').trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        while let Some(line) = lines.next() {
+            let fields: Vec<_> = line.split_whitespace().collect();
+            let value = fields.get(1).copied().unwrap_or("");
+            match fields[0] {
+                "FLAG" => aff.flag_type = Some(match value {
+                    "long" => FlagType::Long,
+                    "num" => FlagType::Num,
+                    _ => FlagType::Char,
+                }),
+                "AF" if value.parse::<usize>().is_ok() => (),
+                "AF" => {
+                    let flags = aff.parse_flags(value)?;
+                    aff.aliases.push(flags);
+                },
+                "TRY" => aff.try_chars = value.chars().collect(),
+                "KEY" => aff.keys = value.split('|').map(|keys| keys.chars().collect()).collect(),
+                "WORDCHARS" => aff.word_chars = value.chars().collect(),
+                "REP" if fields.len() == 2 => (),
+                "REP" => {
+                    let to = fields.get(2).copied().unwrap_or("");
+                    aff.replacements.push((value.replace('_', " "), to.replace('_', " ")));
+                },
+                "MAP" if value.parse::<usize>().is_ok() => (),
+                "MAP" => aff.map.push(parse_map(value)),
+                "FORBIDDENWORD" => aff.forbidden = Some(aff.parse_flag(value)?),
+                "NOSUGGEST" => aff.no_suggest = Some(aff.parse_flag(value)?),
+                "NEEDAFFIX" | "PSEUDOROOT" => aff.need_affix = Some(aff.parse_flag(value)?),
+                "KEEPCASE" => aff.keep_case = Some(aff.parse_flag(value)?),
+                "ONLYINCOMPOUND" => aff.only_in_compound = Some(aff.parse_flag(value)?),
+                "COMPOUNDFLAG" => aff.compound = Some(aff.parse_flag(value)?),
+                "COMPOUNDBEGIN" => aff.compound_begin = Some(aff.parse_flag(value)?),
+                "COMPOUNDMIDDLE" => aff.compound_middle = Some(aff.parse_flag(value)?),
+                "COMPOUNDEND" | "COMPOUNDLAST" => aff.compound_end = Some(aff.parse_flag(value)?),
+                "COMPOUNDMIN" => aff.compound_min = value.parse().unwrap_or(3).max(1),
+                "COMPOUNDWORDMAX" => aff.compound_max = value.parse().ok(),
+                "PFX" | "SFX" => {
+                    let count = match fields.get(3).and_then(|count| count.parse::<usize>().ok()) {
+                        Some(count) => count,
+                        None => return Err(Error::new(format!("invalid affix header in .aff file: {}", line))),
+                    };
+                    let flag = aff.parse_flag(value)?;
+                    let cross = fields.get(2) == Some(&"Y");
+                    for _ in 0..count {
+                        let rule = lines.next()
+                            .ok_or_else(|| Error::new(format!("missing rules for affix {}", value)))?;
+                        let affix = aff.parse_affix(rule, flag, cross)?;
+                        if fields[0] == "PFX" {
+                            aff.prefixes.push(affix);
+                        } else {
+                            aff.suffixes.push(affix);
+                        }
+                    }
+                },
+                _ => (),
+            }
+        }
+        Ok(aff)
+    }
+
+    /// Parses a rule like `SFX A y ied [^aeiou]y`
+    fn parse_affix(&self, rule: &str, flag: Flag, cross: bool) -> Result<Affix> {
+        let fields: Vec<_> = rule.split_whitespace().collect();
+        if fields.len() < 4 {
+            return Err(Error::new(format!("invalid affix rule in .aff file: {}", rule)));
+        }
+        let empty = |s: &str| if s == "0" { String::new() } else { s.to_owned() };
+        let (add, flags) = match fields[3].find('/') {
+            Some(i) => (&fields[3][..i], self.parse_flags(&fields[3][i + 1..])?),
+            None => (fields[3], vec!()),
+        };
+        Ok(Affix {
+            flag,
+            cross,
+            strip: empty(fields[2]),
+            add: empty(add),
+            condition: parse_condition(fields.get(4).copied().unwrap_or(".")),
+            flags,
+        })
+    }
+
+    /// Parses a single flag
+    fn parse_flag(&self, s: &str) -> Result<Flag> {
+        self.parse_flags(s)?
+            .first()
+            .copied()
+            .ok_or_else(|| Error::new("missing flag in .aff file"))
+    }
+
+    /// Parses a list of flags, or an alias
+    pub fn parse_flags(&self, s: &str) -> Result<Vec<Flag>> {
+        if !self.aliases.is_empty() {
+            if let Ok(n) = s.parse::<usize>() {
+                return self.aliases.get(n.wrapping_sub(1))
+                    .cloned()
+                    .ok_or_else(|| Error::new(format!("unknown flag alias {}", n)));
+            }
+        }
+        match self.flag_type.unwrap_or(FlagType::Char) {
+            FlagType::Char => Ok(s.chars().map(|c| c as Flag).collect()),
+            FlagType::Long => {
+                let chars: Vec<_> = s.chars().collect();
+                Ok(chars.chunks(2)
+                   .map(|pair| ((pair[0] as Flag) << 16) | pair.get(1).map(|c| *c as Flag).unwrap_or(0))
+                   .collect())
+            },
+            FlagType::Num => s.split(',')
+                .filter(|n| !n.is_empty())
+                .map(|n| n.parse().map_err(|_| Error::new(format!("invalid numeric flag '{}'", n))))
+                .collect(),
+        }
+    }
+}
+
+/// Parses a condition like `[^aeiou]y`
+fn parse_condition(s: &str) -> Vec<Condition> {
+    let mut condition = vec!();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => condition.push(Condition::Any),
+            '[' => {
+                let mut set = vec!();
+                let mut negate = false;
+                for c in chars.by_ref() {
+                    match c {
+                        ']' => break,
+                        '^' if set.is_empty() && !negate => negate = true,
+                        c => set.push(c),
+                    }
+                }
+                condition.push(if negate { Condition::NotSet(set) } else { Condition::Set(set) });
+            },
+            c => condition.push(Condition::Char(c)),
+        }
+    }
+    condition
+}
+
+/// Parses a group of related characters like `aàâ` or `u(ü)(ue)`
+fn parse_map(s: &str) -> Vec<String> {
+    let mut group = vec!();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '(' {
+            group.push(chars.by_ref().take_while(|c| *c != ')').collect());
+        } else {
+            group.push(c.to_string());
+        }
+    }
+    group
+}
+
+/// Parses a `.dic` file, returning the flags of each word (a word can have several entries)
+pub(crate) fn parse_dic(text: &str, aff: &AffixFile) -> Result<HashMap<String, Vec<Vec<Flag>>>> {
+    let mut words: HashMap<String, Vec<Vec<Flag>>> = HashMap::new();
+    // The first line is the (approximate) number of words
+    for line in text.lines().skip(1) {
+        let line = line.trim_end();
+        // Morphological fields come after a tab or a space
+        let entry = line.split(['\t', ' ']).next().unwrap_or("");
+        if entry.is_empty() {
+            continue;
+        }
+        // A slash that is part of the word is escaped
+        let mut word = String::new();
+        let mut flags = "";
+        let mut chars = entry.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => if let Some((_, c)) = chars.next() { word.push(c) },
+                '/' => {
+                    flags = &entry[i + 1..];
+                    break;
+                },
+                c => word.push(c),
+            }
+        }
+        let flags = aff.parse_flags(flags)?;
+        words.entry(word).or_default().push(flags);
+    }
+    Ok(words)
+}
+
+/// Decodes an `.aff` or `.dic` file, according to the `SET` option of the `.aff` file
+pub(crate) fn decode(bytes: &[u8], encoding: &str) -> Result<String> {
+    match encoding.to_uppercase().as_str() {
+        "" | "UTF-8" | "UTF8" => Ok(String::from_utf8_lossy(bytes).into_owned()),
+        "ISO8859-1" | "ISO-8859-1" => Ok(bytes.iter().map(|b| *b as char).collect()),
+        "ISO8859-15" | "ISO-8859-15" => Ok(bytes.iter()
+            .map(|b| match *b {
+                0xA4 => '€',
+                0xA6 => 'Š',
+                0xA8 => 'š',
+                0xB4 => 'Ž',
+                0xB8 => 'ž',
+                0xBC => 'Œ',
+                0xBD => 'œ',
+                0xBE => 'Ÿ',
+                b => b as char,
+            })
+            .collect()),
+        other => Err(Error::unsupported(format!("unsupported dictionary encoding '{}'", other))),
+    }
+}
+
+/// Returns the value of the `SET` option of an `.aff` file
+pub(crate) fn encoding(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .find(|line| line.trim_start_matches('\u{feff}').starts_with("SET "))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("")
+        .to_owned()
+}
+
+
+#[test]
+fn parse_affixes() {
+    let aff = AffixFile::parse("SET UTF-8\nSFX S Y 2\nSFX S y ies [^aeiou]y\nSFX S 0 s [^y]\n\
+                                PFX U N 1\nPFX U 0 un .\nREP 1\nREP f ph\nMAP 1\nMAP u(ü)\n").unwrap();
+    assert_eq!(aff.suffixes.len(), 2);
+    assert_eq!(aff.suffixes[0].strip_suffix("flies").as_deref(), Some("fly"));
+    assert_eq!(aff.suffixes[0].strip_suffix("plays"), None);
+    assert_eq!(aff.suffixes[1].strip_suffix("cats").as_deref(), Some("cat"));
+    assert_eq!(aff.prefixes[0].strip_prefix("undo").as_deref(), Some("do"));
+    assert!(!aff.prefixes[0].cross);
+    assert_eq!(aff.replacements, vec!(("f".to_owned(), "ph".to_owned())));
+    assert_eq!(aff.map, vec!(vec!("u".to_owned(), "ü".to_owned())));
+
+    let aff = AffixFile::parse("FLAG long\nAF 2\nAF AaBb\nAF Cc\n").unwrap();
+    assert_eq!(aff.parse_flags("2").unwrap(), aff.parse_flags("Cc").unwrap());
+    let dic = parse_dic("2\nfoo/1\tpo:noun\nand\\/or\n", &aff).unwrap();
+    assert_eq!(dic["foo"][0].len(), 2);
+    assert!(dic.contains_key("and/or"));
+}
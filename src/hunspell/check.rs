@@ -0,0 +1,204 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Lookup of words, with affixes and compounds
+
+use super::HunspellDictionary;
+use super::aff::Flag;
+
+/// How a correct word was found
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Correct {
+    /// The word is in the dictionary
+    Ok,
+    /// The word is formed by adding affixes to this root
+    Root(String),
+    /// The word is a compound of several words of the dictionary
+    Compound,
+}
+
+/// Returns true if `flags` contains `flag`
+fn has(flags: &[Flag], flag: Option<Flag>) -> bool {
+    flag.map(|flag| flags.contains(&flag)).unwrap_or(false)
+}
+
+impl HunspellDictionary {
+    /// Checks a word, trying other cases if it isn't found as is
+    pub(crate) fn check_word(&self, word: &str) -> Option<Correct> {
+        if let Some(correct) = self.check_case(word, false) {
+            return Some(correct);
+        }
+        let lower = word.to_lowercase();
+        if lower == word {
+            return None;
+        }
+        let capitalized = capitalize(&lower);
+        // "HELLO" can be "Hello" or "hello", "Hello" can only be "hello"
+        let variants = if word.chars().all(|c| !c.is_lowercase()) {
+            vec!(capitalized, lower)
+        } else if word == capitalized {
+            vec!(lower)
+        } else {
+            vec!()
+        };
+        variants.iter()
+            .filter_map(|variant| self.check_case(variant, true))
+            .next()
+    }
+
+    /// Returns true if `word` (or the root it comes from) must not be suggested
+    pub(crate) fn is_no_suggest(&self, word: &str, correct: &Correct) -> bool {
+        let root = match *correct {
+            Correct::Root(ref root) => root.as_str(),
+            _ => word,
+        };
+        self.words.get(root)
+            .map(|entries| entries.iter().any(|flags| has(flags, self.aff.no_suggest)))
+            .unwrap_or(false)
+    }
+
+    /// Checks a word without changing its case
+    ///
+    /// If `variant` is true, the word was not written that way, so words with the
+    /// `KEEPCASE` flag are not accepted.
+    fn check_case(&self, word: &str, variant: bool) -> Option<Correct> {
+        if self.is_forbidden(word) {
+            return None;
+        }
+        let aff = &self.aff;
+        let accept = |flags: &[Flag]| {
+            !has(flags, aff.forbidden)
+                && !has(flags, aff.only_in_compound)
+                && (!variant || !has(flags, aff.keep_case))
+        };
+        if let Some(correct) = self.find(word, &accept, true, true) {
+            return Some(correct);
+        }
+        if self.check_compound(word, true, 0) {
+            return Some(Correct::Compound);
+        }
+        None
+    }
+
+    /// Returns true if the word is explicitly forbidden
+    fn is_forbidden(&self, word: &str) -> bool {
+        self.words.get(word)
+            .map(|entries| entries.iter().any(|flags| has(flags, self.aff.forbidden)))
+            .unwrap_or(false)
+    }
+
+    /// Looks for a word in the dictionary, or a root it can be derived from
+    ///
+    /// `accept` tells whether the flags of a root allow it to be used.
+    fn find(&self, word: &str, accept: &dyn Fn(&[Flag]) -> bool,
+            allow_prefix: bool, allow_suffix: bool) -> Option<Correct> {
+        let aff = &self.aff;
+        let root_has = |root: &str, flags_needed: &[Flag]| {
+            self.words.get(root)
+                .map(|entries| entries.iter()
+                     .any(|flags| accept(flags) && flags_needed.iter().all(|flag| flags.contains(flag))))
+                .unwrap_or(false)
+        };
+
+        if let Some(entries) = self.words.get(word) {
+            if entries.iter().any(|flags| accept(flags) && !has(flags, aff.need_affix)) {
+                return Some(Correct::Ok);
+            }
+        }
+
+        if allow_suffix {
+            for suffix in &aff.suffixes {
+                let root = match suffix.strip_suffix(word) {
+                    Some(root) => root,
+                    None => continue,
+                };
+                if root_has(&root, &[suffix.flag]) {
+                    return Some(Correct::Root(root));
+                }
+                // Twofold suffixes: the suffix is allowed by another suffix of the root
+                for inner in aff.suffixes.iter().filter(|inner| inner.flags.contains(&suffix.flag)) {
+                    if let Some(inner_root) = inner.strip_suffix(&root) {
+                        if root_has(&inner_root, &[inner.flag]) {
+                            return Some(Correct::Root(inner_root));
+                        }
+                    }
+                }
+            }
+        }
+
+        if allow_prefix {
+            for prefix in &aff.prefixes {
+                let root = match prefix.strip_prefix(word) {
+                    Some(root) => root,
+                    None => continue,
+                };
+                if root_has(&root, &[prefix.flag]) {
+                    return Some(Correct::Root(root));
+                }
+                if !allow_suffix {
+                    continue;
+                }
+                for suffix in &aff.suffixes {
+                    let inner_root = match suffix.strip_suffix(&root) {
+                        Some(inner_root) => inner_root,
+                        None => continue,
+                    };
+                    // Either both affixes allow cross products, or the suffix allows the prefix
+                    if (prefix.cross && suffix.cross && root_has(&inner_root, &[prefix.flag, suffix.flag]))
+                        || (suffix.flags.contains(&prefix.flag) && root_has(&inner_root, &[suffix.flag])) {
+                        return Some(Correct::Root(inner_root));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Checks whether a word is a compound of several words
+    ///
+    /// `first` is true if `word` is at the beginning of the compound, and `parts` is
+    /// the number of parts before `word`.
+    fn check_compound(&self, word: &str, first: bool, parts: usize) -> bool {
+        let aff = &self.aff;
+        if aff.compound.is_none() && aff.compound_begin.is_none() {
+            return false;
+        }
+        let part_of = |flag: Option<Flag>| move |flags: &[Flag]| {
+            !has(flags, aff.forbidden) && (has(flags, aff.compound) || has(flags, flag))
+        };
+        let head_flag = if first { aff.compound_begin } else { aff.compound_middle };
+        let len = word.chars().count();
+        let can_continue = aff.compound_max.map(|max| parts + 3 <= max).unwrap_or(true);
+
+        for (n, (i, _)) in word.char_indices().enumerate() {
+            if n < aff.compound_min {
+                continue;
+            }
+            if len - n < aff.compound_min {
+                break;
+            }
+            let (head, tail) = word.split_at(i);
+            // Prefixes are only allowed at the beginning of the compound, suffixes at its end
+            if self.find(head, &part_of(head_flag), first, false).is_none() {
+                continue;
+            }
+            if self.find(tail, &part_of(aff.compound_end), false, true).is_some() {
+                return true;
+            }
+            if can_continue && self.check_compound(tail, false, parts + 1) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Puts the first letter of a word in uppercase
+pub(crate) fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,288 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+mod aff;
+mod check;
+mod suggest;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::backend::SpellBackend;
+use crate::backend_info::{BackendInfo, Flavour};
+use crate::error::{Result, Error};
+use crate::ispell_result::{IspellResult, IspellError, RejectedWord};
+use crate::position::{Position, Unit};
+use crate::protocol;
+
+use self::aff::{AffixFile, Flag};
+use self::check::Correct;
+
+/// Directories where Hunspell dictionaries are usually installed
+const HUNSPELL_DIRS: &[&str] = &["/usr/share/hunspell", "/usr/local/share/hunspell",
+                                 "/usr/share/myspell", "/usr/share/myspell/dicts",
+                                 "/opt/local/share/hunspell", "/Library/Spelling"];
+
+/// A spell checker that reads Hunspell dictionaries, without spawning a process
+///
+/// It loads a pair of `.aff` (affixes and options) and `.dic` (words) files, and
+/// checks lines the same way `SpellChecker` does with `hunspell`, returning the same
+/// `IspellResult`s: `Ok` for a word of the dictionary, `Root` for a word derived from
+/// a root with affixes, `Compound` for a compound word, and `Miss` or `None` for a
+/// misspelled word, depending on whether there are suggestions.
+///
+/// Only the most common options of `.aff` files are supported: prefixes and suffixes
+/// (including twofold suffixes and cross products), compounding (`COMPOUNDFLAG`,
+/// `COMPOUNDBEGIN`, `COMPOUNDMIDDLE`, `COMPOUNDEND`, `COMPOUNDMIN`, `COMPOUNDWORDMAX`),
+/// `FORBIDDENWORD`, `NEEDAFFIX`, `KEEPCASE`, `ONLYINCOMPOUND`, `NOSUGGEST`, and `REP`, `MAP`,
+/// `KEY` and `TRY` for suggestions. Others are ignored.
+///
+/// # Example
+///
+/// ```
+/// use ispell::{HunspellDictionary, IspellResult, SpellBackend};
+/// let aff = "SET UTF-8\nTRY esianrtolcdugmphbyfvkwz\nSFX S Y 1\nSFX S 0 s .\n";
+/// let dic = "2\nword/S\nspell\n";
+/// let mut checker = HunspellDictionary::new(aff, dic).unwrap();
+/// let results = checker.check_raw("spell words wrod").unwrap();
+/// assert_eq!(results[0], IspellResult::Ok);
+/// assert_eq!(results[1], IspellResult::Root("word".to_owned()));
+/// match results[2] {
+///     IspellResult::Miss(ref error) => assert_eq!(error.suggestions, vec!("word")),
+///     _ => panic!("unexpected result"),
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HunspellDictionary {
+    aff: AffixFile,
+    words: HashMap<String, Vec<Vec<Flag>>>,
+    info: BackendInfo,
+    /// The personal dictionary, if one was set
+    personal: Option<PathBuf>,
+    /// Words added to the personal dictionary that haven't been saved yet
+    unsaved: Vec<String>,
+}
+
+impl HunspellDictionary {
+    /// Creates a checker from the contents of an `.aff` and a `.dic` file
+    pub fn new(aff: &str, dic: &str) -> Result<HunspellDictionary> {
+        let aff = AffixFile::parse(aff)?;
+        let words = aff::parse_dic(dic, &aff)?;
+        Ok(HunspellDictionary {
+            aff,
+            words,
+            info: BackendInfo {
+                flavour: Flavour::Hunspell,
+                ispell_version: None,
+                version: None,
+                banner: format!("Hunspell dictionary (read by rust-ispell {})", env!("CARGO_PKG_VERSION")),
+            },
+            personal: None,
+            unsaved: vec!(),
+        })
+    }
+
+    /// Loads an `.aff` and a `.dic` file
+    ///
+    /// They are decoded according to the `SET` option of the `.aff` file (only
+    /// `UTF-8`, `ISO8859-1` and `ISO8859-15` are supported).
+    pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(aff: P, dic: Q) -> Result<HunspellDictionary> {
+        let aff = fs::read(aff)?;
+        let dic = fs::read(dic)?;
+        let encoding = aff::encoding(&aff);
+        HunspellDictionary::new(&aff::decode(&aff, &encoding)?, &aff::decode(&dic, &encoding)?)
+    }
+
+    /// Loads an installed dictionary, e.g. `en_US`
+    ///
+    /// Like `hunspell`, it looks for `name.aff` and `name.dic` in the directories of the
+    /// `DICPATH` environment variable, then in the usual installation directories.
+    pub fn load(name: &str) -> Result<HunspellDictionary> {
+        let dirs = env::var_os("DICPATH")
+            .map(|path| env::split_paths(&path).collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .chain(HUNSPELL_DIRS.iter().map(PathBuf::from));
+        for dir in dirs {
+            let aff = dir.join(format!("{}.aff", name));
+            let dic = dir.join(format!("{}.dic", name));
+            if aff.is_file() && dic.is_file() {
+                return HunspellDictionary::open(aff, dic);
+            }
+        }
+        Err(Error::no_dictionary(name, format!("could not find Hunspell dictionary '{}'", name)))
+    }
+
+    /// Sets the personal dictionary, where words added with `add_word_to_dictionary`
+    /// are saved
+    ///
+    /// If the file exists, its words are added to the current session. Like with
+    /// `hunspell`, it contains one word per line.
+    pub fn set_personal_dictionary<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if path.exists() {
+            let text = fs::read_to_string(path)?;
+            // Lines can also be `*word` (forbidden word) or `word/model`: only keep words
+            for line in text.lines().filter(|line| !line.starts_with('*')) {
+                let word = line.split('/').next().unwrap_or("").trim();
+                if !word.is_empty() {
+                    self.words.entry(word.to_owned()).or_default().push(vec!());
+                }
+            }
+        }
+        self.personal = Some(path.to_owned());
+        Ok(())
+    }
+
+    /// Splits a line into words, returning each of them with its offset
+    fn words<'a>(&self, line: &'a str) -> Vec<(usize, &'a str)> {
+        let is_word_char = |c: char| c.is_alphanumeric() || self.aff.word_chars.contains(&c);
+        let mut words = vec!();
+        let mut start = None;
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            // Apostrophes are part of a word if they are followed by a letter
+            let apostrophe = (c == '\'' || c == '’') && start.is_some()
+                && chars.peek().map(|&(_, next)| next.is_alphabetic()).unwrap_or(false);
+            if is_word_char(c) || apostrophe {
+                start.get_or_insert(i);
+            } else if let Some(start) = start.take() {
+                words.push((start, &line[start..i]));
+            }
+        }
+        if let Some(start) = start {
+            words.push((start, &line[start..]));
+        }
+        words.into_iter()
+            .map(|(start, word)| {
+                let trimmed = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+                (start + word.len() - trimmed.len(), trimmed.trim_end_matches(|c: char| !c.is_alphanumeric()))
+            })
+            .filter(|(_, word)| word.chars().any(|c| c.is_alphabetic()))
+            .collect()
+    }
+}
+
+impl SpellBackend for HunspellDictionary {
+    fn check_raw(&mut self, text: &str) -> Result<Vec<IspellResult>> {
+        let mut results = vec!();
+        for (offset, word) in self.words(text) {
+            let correct = self.check_word(&word.replace('’', "'"));
+            results.push(match correct {
+                Some(Correct::Ok) => IspellResult::Ok,
+                Some(Correct::Root(root)) => IspellResult::Root(root),
+                Some(Correct::Compound) => IspellResult::Compound,
+                None => {
+                    let error = IspellError {
+                        misspelled: word.to_owned(),
+                        position: Position::from_offset(text, offset, Unit::Byte)
+                            .expect("word offsets are on character boundaries"),
+                        suggestions: self.suggest(word),
+                    };
+                    if error.suggestions.is_empty() {
+                        IspellResult::None(error)
+                    } else {
+                        IspellResult::Miss(error)
+                    }
+                },
+            });
+        }
+        Ok(results)
+    }
+
+    fn add_word(&mut self, word: &str) -> Result<()> {
+        protocol::validate_word(word)?;
+        self.words.entry(word.to_owned()).or_default().push(vec!());
+        Ok(())
+    }
+
+    /// Adds words to the session and to the words that will be saved by `save_dictionary`
+    ///
+    /// Words are rejected if they are empty, contain spaces, or would not be read as a
+    /// single word when checking a line.
+    fn add_words_to_dictionary(&mut self, words: &[&str]) -> Result<Vec<RejectedWord>> {
+        let mut rejected = vec!();
+        for &word in words {
+            let reason = match protocol::validate_word(word) {
+                Ok(()) if self.words(word) != [(0, word)] =>
+                    Some(format!("word '{}' is not a single word for the spell checker", word)),
+                Ok(()) => None,
+                Err(err) => Some(err.to_string()),
+            };
+            match reason {
+                Some(reason) => rejected.push(RejectedWord {
+                    word: word.to_owned(),
+                    reason,
+                }),
+                None => {
+                    self.add_word(word)?;
+                    self.unsaved.push(word.to_owned());
+                },
+            }
+        }
+        Ok(rejected)
+    }
+
+    /// Appends the words that were added since the last save to the personal dictionary
+    ///
+    /// Returns an error if these words can't be saved because no personal dictionary
+    /// was set (see `set_personal_dictionary`).
+    fn save_dictionary(&mut self) -> Result<()> {
+        if self.unsaved.is_empty() {
+            return Ok(());
+        }
+        let path = match self.personal {
+            Some(ref path) => path,
+            None => return Err(Error::new("no personal dictionary was set")),
+        };
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        for word in &self.unsaved {
+            writeln!(file, "{}", word)?;
+        }
+        self.unsaved.clear();
+        Ok(())
+    }
+
+    fn backend_info(&self) -> &BackendInfo {
+        &self.info
+    }
+}
+
+
+#[test]
+fn check_words() {
+    let aff = "SET UTF-8\n\
+               TRY esianrtolcdugmphbyfvkwz'\n\
+               KEY qwertyuiop|asdfghjkl|zxcvbnm\n\
+               REP 1\nREP f ph\n\
+               MAP 1\nMAP eé\n\
+               FORBIDDENWORD !\nNOSUGGEST X\n\
+               COMPOUNDFLAG C\nCOMPOUNDMIN 3\n\
+               PFX U Y 1\nPFX U 0 un .\n\
+               SFX S Y 2\nSFX S y ies [^aeiou]y\nSFX S 0 s [^y]\n\
+               SFX D Y 1\nSFX D 0 ed/S .\n";
+    let dic = "10\nfly/S\nlock/USD\ntest/S\nfoot/C\nball/CS\nphone\ncafé\nParis\nshit/X\ntests/!\n";
+    let mut checker = HunspellDictionary::new(aff, dic).unwrap();
+
+    let results = checker.check_raw("Flies unlocks lockeds footballs Paris").unwrap();
+    assert_eq!(results, vec!(IspellResult::Root("fly".to_owned()),
+                             IspellResult::Root("lock".to_owned()),
+                             IspellResult::Root("lock".to_owned()),
+                             IspellResult::Compound,
+                             IspellResult::Ok));
+
+    checker.add_word("xyzzy").unwrap();
+    let errors = checker.check("Fone tests cafe paris shitt xyzzy").unwrap();
+    let suggestions: Vec<_> = errors.iter()
+        .map(|error| (error.misspelled.as_str(), error.position.char(), error.suggestions.clone()))
+        .collect();
+    assert_eq!(suggestions, vec!(("Fone", 0, vec!("Phone".to_owned())),
+                                 ("tests", 5, vec!("test".to_owned())),
+                                 ("cafe", 11, vec!("café".to_owned())),
+                                 ("paris", 16, vec!("Paris".to_owned())),
+                                 ("shitt", 22, vec!())));
+}
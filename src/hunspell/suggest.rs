@@ -0,0 +1,157 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Suggestions for misspelled words
+
+use super::HunspellDictionary;
+use super::check::capitalize;
+
+/// Maximum number of suggestions for a word
+const MAX_SUGGESTIONS: usize = 15;
+
+/// Maximum number of words generated with `MAP`
+const MAX_MAP_VARIANTS: usize = 100;
+
+impl HunspellDictionary {
+    /// Returns suggestions for a misspelled word, best ones first
+    pub(crate) fn suggest(&self, word: &str) -> Vec<String> {
+        let mut suggestions = vec!();
+        self.suggest_for(word, &mut suggestions, &|s| s.to_owned());
+
+        // "Helo" and "HELO" are corrected as "helo", and corrections take the same case
+        let lower = word.to_lowercase();
+        if lower != word {
+            let upper = word.chars().all(|c| !c.is_lowercase());
+            let restore = |s: &str| if upper { s.to_uppercase() } else { capitalize(s) };
+            self.suggest_for(&lower, &mut suggestions, &restore);
+        }
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+
+    /// Adds the suggestions for `word` to `suggestions`, after changing their case with `restore`
+    fn suggest_for(&self, word: &str, suggestions: &mut Vec<String>, restore: &dyn Fn(&str) -> String) {
+        let mut add = |candidate: String| {
+            let candidate = restore(&candidate);
+            if candidate != word && !suggestions.contains(&candidate) && self.is_suggestible(&candidate) {
+                suggestions.push(candidate);
+            }
+        };
+        let chars: Vec<char> = word.chars().collect();
+        let aff = &self.aff;
+
+        // Wrong case
+        add(capitalize(word));
+
+        // Common mistakes, e.g. "f" instead of "ph"
+        for (from, to) in &aff.replacements {
+            let (from, at_start) = match from.strip_prefix('^') {
+                Some(from) => (from, true),
+                None => (from.as_str(), false),
+            };
+            let (from, at_end) = match from.strip_suffix('$') {
+                Some(from) => (from, true),
+                None => (from, false),
+            };
+            if from.is_empty() {
+                continue;
+            }
+            for (i, _) in word.match_indices(from) {
+                if (at_start && i != 0) || (at_end && i + from.len() != word.len()) {
+                    continue;
+                }
+                add(format!("{}{}{}", &word[..i], to, &word[i + from.len()..]));
+            }
+        }
+
+        // Related characters, e.g. accents
+        let mut variants = vec!();
+        self.map_variants(word, 0, &mut variants);
+        for variant in variants {
+            add(variant);
+        }
+
+        // Neighbour keys
+        for (i, c) in chars.iter().enumerate() {
+            for keys in &aff.keys {
+                for (k, key) in keys.iter().enumerate() {
+                    if key != c {
+                        continue;
+                    }
+                    for neighbour in k.checked_sub(1).and_then(|k| keys.get(k)).into_iter().chain(keys.get(k + 1)) {
+                        add(replace(&chars, i, 1, &[*neighbour]));
+                    }
+                }
+            }
+        }
+
+        // Swapped characters
+        for i in 1..chars.len() {
+            let mut swapped = chars.clone();
+            swapped.swap(i - 1, i);
+            add(swapped.iter().collect());
+        }
+
+        // Extra character
+        for i in 0..chars.len() {
+            add(replace(&chars, i, 1, &[]));
+        }
+
+        // Forgotten character
+        for i in 0..=chars.len() {
+            for c in &aff.try_chars {
+                add(replace(&chars, i, 0, &[*c]));
+            }
+        }
+
+        // Wrong character
+        for i in 0..chars.len() {
+            for c in aff.try_chars.iter().filter(|c| **c != chars[i]) {
+                add(replace(&chars, i, 1, &[*c]));
+            }
+        }
+
+        // Missing space
+        for i in 1..chars.len() {
+            add(replace(&chars, i, 0, &[' ']));
+        }
+    }
+
+    /// Adds to `variants` the words that can be formed by replacing characters from
+    /// `start` with related ones (see `MAP`)
+    fn map_variants(&self, word: &str, start: usize, variants: &mut Vec<String>) {
+        for (i, _) in word.char_indices().filter(|(i, _)| *i >= start) {
+            for group in &self.aff.map {
+                for from in group.iter().filter(|from| word[i..].starts_with(from.as_str())) {
+                    for to in group.iter().filter(|to| *to != from) {
+                        if variants.len() >= MAX_MAP_VARIANTS {
+                            return;
+                        }
+                        let variant = format!("{}{}{}", &word[..i], to, &word[i + from.len()..]);
+                        self.map_variants(&variant, i + to.len(), variants);
+                        variants.push(variant);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns true if a candidate is correct and can be suggested
+    ///
+    /// Candidates made of several words are accepted if all their words are.
+    fn is_suggestible(&self, candidate: &str) -> bool {
+        candidate.split(' ').all(|word| match self.check_word(word) {
+            Some(ref correct) => !self.is_no_suggest(word, correct),
+            None => false,
+        })
+    }
+}
+
+/// Replaces `len` characters at `i` by `with`
+fn replace(chars: &[char], i: usize, len: usize, with: &[char]) -> String {
+    chars[..i].iter()
+        .chain(with)
+        .chain(&chars[i + len..])
+        .collect()
+}
@@ -3,24 +3,76 @@
 // this file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 
+use crate::position::Position;
+
 /// An ispell error, corresponding to a word that isn't in the dictonary.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IspellError {
     /// The misspelled word
     pub misspelled: String,
 
-    /// The position of the word
-    /// (number of characters since the beginning of the new line)
-    pub position: usize,
+    /// The position of the word since the beginning of the line
+    pub position: Position,
 
     /// A list of suggestions
     pub suggestions: Vec<String>,
 }
 
+/// An ispell error located in a whole document
+///
+/// Returned by `SpellChecker::check_document`. Lines and columns both start at 0.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocumentError {
+    /// The error, as reported by ispell for this line
+    pub error: IspellError,
+
+    /// The line where the error was found
+    pub line: usize,
+
+    /// The position of the word since the beginning of the line
+    pub column: Position,
+
+    /// The position of the word since the beginning of the document
+    pub offset: Position,
+}
+
+impl DocumentError {
+    /// Locates an error reported for a line that starts at `line_start` in the document
+    pub(crate) fn new(error: IspellError, line: usize, line_start: Position) -> DocumentError {
+        let column = error.position;
+        DocumentError {
+            error,
+            line,
+            column,
+            offset: line_start + column,
+        }
+    }
+}
+
+/// A word that could not be added to a dictionary
+///
+/// Returned by `SpellChecker::add_words_to_dictionary`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RejectedWord {
+    /// The word
+    pub word: String,
+
+    /// Why it was rejected
+    pub reason: String,
+}
+
 /// A result from ispell, corresponding to a line that is sent back for each word.
 ///
 /// See the manpage `ispell(1)` for more informations about the meaning of each variant.
+///
+/// With the `serde` feature, it is serialized with the name of the variant in `type`,
+/// and its content (if any) in `value`, e.g. `{"type": "Root", "value": "test"}`.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum IspellResult {
     /// The word was found in the dictionnary.
     ///
@@ -0,0 +1,179 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::spell_launcher::{SpellLauncher, Mode, Format};
+
+/// The configuration of a `SpellLauncher`, as plain data
+///
+/// Each field corresponds to a method of `SpellLauncher`, and fields that are not
+/// set keep the value the launcher already had. With the `serde` feature, this can be
+/// loaded from a configuration file (every field is optional, and `backend` and
+/// `format` are written in lower case), then applied with `SpellLauncher::config`.
+///
+/// # Example
+///
+/// ```no_run
+/// use ispell::{SpellLauncher, LauncherConfig, Mode};
+/// let config = LauncherConfig {
+///     backend: Some(Mode::Hunspell),
+///     dictionary: Some("en_US".to_owned()),
+///     ..LauncherConfig::default()
+/// };
+/// let checker = SpellLauncher::new()
+///               .config(&config)
+///               .launch()
+///               .unwrap();
+/// ```
+///
+/// With the `serde` feature (and e.g. `serde_json`):
+///
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// use ispell::{LauncherConfig, Format};
+/// let config: LauncherConfig = serde_json::from_str(r#"{
+///     "backend": "aspell",
+///     "language": "en-GB",
+///     "format": "tex",
+///     "args": ["--sug-mode=ultra"]
+/// }"#).unwrap();
+/// assert_eq!(config.format, Some(Format::Tex));
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct LauncherConfig {
+    /// The program to run (see `SpellLauncher::mode`)
+    pub backend: Option<Mode>,
+
+    /// Whether to detect the program to run (see `SpellLauncher::auto`), if `backend`
+    /// isn't set
+    pub auto: bool,
+
+    /// A custom command to run (see `SpellLauncher::command`)
+    pub command: Option<String>,
+
+    /// The name of the dictionary (see `SpellLauncher::dictionary`)
+    pub dictionary: Option<String>,
+
+    /// A BCP-47 language tag (see `SpellLauncher::language`)
+    pub language: Option<String>,
+
+    /// The file of the personal dictionary (see `SpellLauncher::personal_dictionary`)
+    pub personal_dictionary: Option<PathBuf>,
+
+    /// The format of the checked text (see `SpellLauncher::format`)
+    pub format: Option<Format>,
+
+    /// Whether to enable the terse mode (see `SpellLauncher::terse`)
+    pub terse: Option<bool>,
+
+    /// Timeout in milliseconds (see `SpellLauncher::timeout`)
+    pub timeout: Option<u64>,
+
+    /// Timeout for a batch of lines, in milliseconds (see `SpellLauncher::batch_timeout`)
+    pub batch_timeout: Option<u64>,
+
+    /// Maximal number of automatic restarts (see `SpellLauncher::auto_restart`)
+    pub max_restarts: Option<u32>,
+
+    /// Initial delay before a restart, in milliseconds (see `SpellLauncher::auto_restart`)
+    pub backoff: Option<u64>,
+
+    /// Extra arguments (see `SpellLauncher::args`)
+    pub args: Vec<String>,
+
+    /// Extra environment variables (see `SpellLauncher::env`)
+    pub env: BTreeMap<String, String>,
+
+    /// Working directory (see `SpellLauncher::current_dir`)
+    pub current_dir: Option<PathBuf>,
+}
+
+impl LauncherConfig {
+    /// Applies this configuration to `launcher`
+    pub(crate) fn apply(&self, launcher: &mut SpellLauncher) {
+        if let Some(mode) = self.backend {
+            launcher.mode(mode);
+        } else if self.auto {
+            launcher.auto();
+        }
+        if let Some(ref command) = self.command {
+            launcher.command(command.as_str());
+        }
+        if let Some(ref dictionary) = self.dictionary {
+            launcher.dictionary(dictionary.as_str());
+        }
+        if let Some(ref language) = self.language {
+            launcher.language(language.as_str());
+        }
+        if let Some(ref path) = self.personal_dictionary {
+            launcher.personal_dictionary(path);
+        }
+        if let Some(format) = self.format {
+            launcher.format(format);
+        }
+        if let Some(terse) = self.terse {
+            launcher.set_terse(terse);
+        }
+        if let Some(timeout) = self.timeout {
+            launcher.timeout(timeout);
+        }
+        if let Some(timeout) = self.batch_timeout {
+            launcher.batch_timeout(timeout);
+        }
+        if self.max_restarts.is_some() || self.backoff.is_some() {
+            let (max_restarts, backoff) = launcher.restart_policy();
+            launcher.auto_restart(self.max_restarts.unwrap_or(max_restarts),
+                                  self.backoff.unwrap_or(backoff.as_millis() as u64));
+        }
+        launcher.args(&self.args);
+        for (key, value) in &self.env {
+            launcher.env(key, value);
+        }
+        if let Some(ref dir) = self.current_dir {
+            launcher.current_dir(dir);
+        }
+    }
+}
+
+
+#[test]
+fn apply_set_fields() {
+    let mut launcher = SpellLauncher::new();
+    launcher.aspell().auto_restart(3, 10);
+    launcher.config(&LauncherConfig {
+        max_restarts: Some(0),
+        ..LauncherConfig::default()
+    });
+    assert_eq!(launcher.get_mode(), Some(Mode::Aspell));
+    assert_eq!(launcher.restart_policy(), (0, std::time::Duration::from_millis(10)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize() {
+    use crate::ispell_result::{IspellResult, IspellError};
+    use crate::position::{Position, Unit};
+
+    let config: LauncherConfig = serde_json::from_str(r#"{"backend": "aspell", "format": "tex", "max_restarts": 2}"#)
+        .unwrap();
+    assert_eq!(config.backend, Some(Mode::Aspell));
+    assert_eq!(config.format, Some(Format::Tex));
+    assert_eq!(config.max_restarts, Some(2));
+    assert!(config.dictionary.is_none());
+
+    let result = IspellResult::Miss(IspellError {
+        misspelled: "thit".to_owned(),
+        position: Position::from_offset("a thit", 2, Unit::Byte).unwrap(),
+        suggestions: vec!("this".to_owned()),
+    });
+    let json = serde_json::to_string(&result).unwrap();
+    assert_eq!(json, r#"{"type":"Miss","value":{"misspelled":"thit","position":{"byte":2,"char":2,"utf16":2},"suggestions":["this"]}}"#);
+    assert_eq!(serde_json::from_str::<IspellResult>(&json).unwrap(), result);
+    assert_eq!(serde_json::to_string(&IspellResult::Ok).unwrap(), r#"{"type":"Ok"}"#);
+}
@@ -7,7 +7,7 @@
 //!
 //! # Example
 //!
-//! ```
+//! ```no_run
 //! use ispell::SpellLauncher;
 //! let mut checker = SpellLauncher::new()
 //!                  .aspell()
@@ -15,7 +15,7 @@
 //!                  .unwrap();
 //! let errors = checker.check("Testing iff if it works").unwrap();
 //! assert_eq!(&errors[0].misspelled, "iff");
-//! assert_eq!(errors[0].position, 8);
+//! assert_eq!(errors[0].position.char(), 8);
 //! ```
 //!
 //! # The `SpellLauncher`
@@ -37,9 +37,21 @@
 //!              .launch();
 //! ```
 //!
-//! You can also set the dictionary that must be used:
+//! If you don't know which one is installed, the `auto` method will pick the
+//! first one that works:
 //!
+//! ```no_run
+//! # use ispell::SpellLauncher;
+//! let checker = SpellLauncher::new()
+//!               .auto()
+//!               .launch()
+//!               .unwrap();
+//! println!("Using {:?}", checker.mode());
 //! ```
+//!
+//! You can also set the dictionary that must be used:
+//!
+//! ```no_run
 //! # use ispell::SpellLauncher;
 //! let checker = SpellLauncher::new()
 //!               .aspell()
@@ -56,19 +68,18 @@
 //!
 //! The main usage of this struct is using the `check` method to get
 //! the errors (`IspellError`) the spell checker detects. The `ispell`
-//! API returns the position (in
-//! characters) from the beginning of the line. This means that, if
+//! API returns the position from the beginning of the line. This means that, if
 //! you want to be able do to anything with these numbers, you'll have
-//! to call `check` line by line.
+//! to call `check` line by line (or use `check_document`).
 //!
 //! This method returns a list of `IspellError`s, containing:
 //!
 //! * the misspelled word;
-//! * the position (number of characters since the beginning of the
-//! line);
+//! * the position since the beginning of the line, which is a `Position`
+//!   that can be expressed in bytes, characters or UTF-16 code units;
 //! * a (possibly empty) list of suggestions.
 //!
-//! ```
+//! ```no_run
 //! # use ispell::SpellLauncher;
 //! let mut checker = SpellLauncher::new()
 //!                   .launch()
@@ -80,6 +91,20 @@
 //! }
 //! ```
 //!
+//! If you want to check a whole document, you can use `check_document`, which
+//! splits it into lines and returns `DocumentError`s, that also contain the line, the
+//! column and the offset of each error:
+//!
+//! ```no_run
+//! # use ispell::SpellLauncher;
+//! let mut checker = SpellLauncher::new()
+//!                   .launch()
+//!                   .unwrap();
+//! let errors = checker.check_document("First line is correct,\nbut not thit one").unwrap();
+//! assert_eq!(errors[0].line, 1);
+//! assert_eq!(errors[0].column.char(), 8);
+//! ```
+//!
 //! `SpellChecker` also provides the `check_raw` method, whose behaviour mimics more closely
 //! ispell's output.
 //!
@@ -89,9 +114,13 @@
 //!
 //! * `add_word` adds a word to this current session, but doesn't save it;
 //! * `add_word_to_dictionary` adds a word to your personal dictionary, saving it for
-//!    next sessions.
+//!   next sessions.
 //!
-//! ```
+//! To add many words to your personal dictionary, `add_words_to_dictionary` adds them
+//! without saving, and returns those that the spell checker rejected; `save_dictionary`
+//! then writes them all at once.
+//!
+//! ```no_run
 //! # use ispell::SpellLauncher;
 //! let mut checker = SpellLauncher::new()
 //!                   .launch()
@@ -102,6 +131,62 @@
 //! assert!(errors.is_empty());
 //! ```
 //!
+//! # Pools
+//!
+//! Since a `SpellChecker` can only check one line at a time, sharing one between
+//! threads requires a mutex. `SpellCheckerPool` runs several checkers with the same
+//! configuration and hands them out to concurrent callers, replacing those whose
+//! process crashed; `LanguagePools` manages one pool per dictionary. Both can also
+//! hold any other `SpellBackend` (see `SpellCheckerPool::with_launcher`).
+//!
+//! ```no_run
+//! # use ispell::{SpellLauncher, SpellCheckerPool};
+//! let pool = SpellCheckerPool::new(&SpellLauncher::new(), 4).unwrap();
+//! pool.add_word("rustaholic").unwrap(); // added to all checkers of the pool
+//! let mut checker = pool.get().unwrap(); // given back to the pool when dropped
+//! assert!(checker.check("rustaholic").unwrap().is_empty());
+//! ```
+//!
+//! # Async
+//!
+//! If you enable the `tokio` feature, `SpellLauncher::launch_async` returns an
+//! `AsyncSpellChecker`, which provides the same methods as `SpellChecker` but
+//! communicates with the spawned process without blocking the current thread
+//! (and without spawning a thread to read its output).
+//!
+//! ```toml
+//! [dependencies]
+//! ispell = { version = "0.3", features = ["tokio"] }
+//! ```
+//!
+//! # Serde
+//!
+//! With the `serde` feature, results (`IspellResult`, `IspellError`, `DocumentError`,
+//! ...) implement `Serialize` and `Deserialize`, and so does `LauncherConfig`, which
+//! allows to load the settings of a `SpellLauncher` from a configuration file.
+//!
+//! # Without spawning a process
+//!
+//! `HunspellDictionary` reads Hunspell `.aff` and `.dic` files directly, so it doesn't
+//! need any program to be installed (only the dictionary). It implements the
+//! `SpellBackend` trait, like `SpellChecker`, and returns the same results. Code
+//! that is generic over `SpellBackend` (including pools, and the `ispell-lsp`
+//! language server with `--backend builtin`) works with both, or with your own
+//! implementation:
+//!
+//! ```no_run
+//! use ispell::{HunspellDictionary, SpellBackend};
+//! let mut checker = HunspellDictionary::load("en_US").unwrap();
+//! let errors = checker.check("Does thit message contain any erors?").unwrap();
+//! assert_eq!(errors.len(), 2);
+//! ```
+//!
+//! # Testing
+//!
+//! With the `mock` feature, `MockBackend` is a fake spell checker with a scripted list
+//! of misspelled words, and the `ispell-mock` binary runs it, so your tests can use
+//! `SpellLauncher::command` without depending on the installed dictionaries.
+//!
 //! # Languages
 //!
 //! `ispell`, `aspell` and `hunspell` all allow you to specify which dictionary must be used,
@@ -126,6 +211,27 @@
 //!
 //! whereas `aspell` accepts both versions.
 //!
+//! If you don't want to deal with these differences, the `language` method accepts
+//! a BCP-47 language tag, and finds the matching dictionary for the program that is
+//! launched:
+//!
+//! ```
+//! # use ispell::SpellLauncher;
+//! let result = SpellLauncher::new()
+//!              .auto()
+//!              .language("en-GB")
+//!              .launch();
+//! ```
+//!
+//! To know which dictionaries are installed, use the `dictionaries` method:
+//!
+//! ```no_run
+//! # use ispell::SpellLauncher;
+//! for dict in SpellLauncher::new().hunspell().dictionaries().unwrap() {
+//!     println!("{} ({:?})", dict.name, dict.language_tag());
+//! }
+//! ```
+//!
 //! # Character encoding
 //!
 //! This library tries to set encoding to `utf-8`, but ispell, hunspell and aspell take different arguments
@@ -135,19 +241,51 @@
 //!
 //! # Requirements
 //!
-//! `rust-ispell` requires the `1.12.0` (or a more recent) version of the
-//! `rustc` compiler, since it uses the `std::sync::mpcs::Receiver::recv_timeout`
-//! that was only stabilized in this version. 
+//! `rust-ispell` requires the `1.58.0` (or a more recent) version of the
+//! `rustc` compiler. The optional features need what their dependencies
+//! require: at the time of writing, `1.71.0` for `tokio`, and `1.88.0`
+//! for `lsp`. Running the tests also needs `1.71.0`, for the dev-dependencies.
 
 mod spell_checker;
 mod spell_launcher;
 mod error;
 mod ispell_result;
 mod async_reader;
+mod batch;
+mod backend_info;
+mod dictionaries;
+mod launcher_config;
+mod position;
+mod protocol;
+mod stderr_buffer;
+mod transcript;
+mod backend;
+mod hunspell;
+#[cfg(feature = "tokio")]
+mod async_spell_checker;
+mod pool;
+#[cfg(feature = "mock")]
+mod mock;
 
 pub use ispell_result::IspellResult;
 pub use ispell_result::IspellError;
+pub use ispell_result::DocumentError;
+pub use ispell_result::RejectedWord;
+pub use position::Position;
 pub use spell_checker::SpellChecker;
-pub use spell_launcher::SpellLauncher;
+pub use backend::SpellBackend;
+pub use hunspell::HunspellDictionary;
+pub use batch::{CheckLines, CheckReader};
+pub use backend_info::{BackendInfo, Flavour};
+pub use dictionaries::DictionaryInfo;
+#[cfg(feature = "tokio")]
+pub use async_spell_checker::AsyncSpellChecker;
+pub use spell_launcher::{SpellLauncher, Mode, Format};
+pub use launcher_config::LauncherConfig;
+pub use transcript::{Transcript, TranscriptEntry, Direction};
+#[cfg(feature = "mock")]
+pub use mock::MockBackend;
+pub use pool::{SpellCheckerPool, PooledSpellChecker, LanguagePools};
 pub use error::Error;
+pub use error::ErrorKind;
 pub use error::Result;
@@ -0,0 +1,262 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// A fake spell checker speaking the `ispell -a` protocol, for tests
+///
+/// It knows the misspelled words it is given (along with their suggestions), and
+/// accepts every other word, so tests don't depend on which program and dictionaries
+/// are installed. It can also simulate latency, crashes and malformed output.
+///
+/// Positions are reported in bytes, and words containing digits can't be added to
+/// the session or the personal dictionary, like with `ispell`.
+///
+/// This is only available with the `mock` feature, which also builds the
+/// `ispell-mock` binary: it runs a `MockBackend` on its stdin and stdout, so it
+/// can be used with `SpellLauncher::command`. Its options (`--misspelled`,
+/// `--words`, `--banner`, `--latency`, `--crash-after` and `--malformed-after`) are
+/// passed with `SpellLauncher::arg`.
+///
+/// # Example
+///
+/// ```
+/// use ispell::MockBackend;
+/// let mut backend = MockBackend::new();
+/// backend.misspelled("thit", &["this", "that"]);
+/// let mut output = vec!();
+/// backend.run(&b"^Is thit ok?\n"[..], &mut output).unwrap();
+/// let output = String::from_utf8(output).unwrap();
+/// assert!(output.ends_with("*\n& thit 2 4: this, that\n*\n\n"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockBackend {
+    banner: String,
+    misspelled: HashMap<String, Vec<String>>,
+    latency: Duration,
+    crash_after: Option<usize>,
+    malformed_after: Option<usize>,
+    personal: Option<PathBuf>,
+}
+
+/// State of a running mock
+struct Session {
+    words: HashSet<String>,
+    /// Words added with `*`, that must be saved with `#`
+    unsaved: Vec<String>,
+    terse: bool,
+    answers: usize,
+}
+
+impl Default for MockBackend {
+    fn default() -> MockBackend {
+        MockBackend::new()
+    }
+}
+
+impl MockBackend {
+    /// Creates a mock that accepts every word
+    pub fn new() -> MockBackend {
+        MockBackend {
+            banner: format!("@(#) International Ispell Version 3.2.06 (but really ispell-mock {})",
+                            env!("CARGO_PKG_VERSION")),
+            misspelled: HashMap::new(),
+            latency: Duration::from_millis(0),
+            crash_after: None,
+            malformed_after: None,
+            personal: None,
+        }
+    }
+
+    /// Sets the first line displayed by the mock
+    pub fn banner<S: Into<String>>(&mut self, banner: S) -> &mut MockBackend {
+        self.banner = banner.into();
+        self
+    }
+
+    /// Adds a misspelled word, with its suggestions
+    pub fn misspelled(&mut self, word: &str, suggestions: &[&str]) -> &mut MockBackend {
+        self.misspelled.insert(word.to_owned(),
+                               suggestions.iter().map(|s| (*s).to_owned()).collect());
+        self
+    }
+
+    /// Adds misspelled words from a list
+    ///
+    /// Each line of `list` contains a word, optionally followed by a colon and
+    /// its suggestions, separated by commas (e.g. `thit: this, that`). Empty lines and
+    /// lines starting with `#` are ignored.
+    pub fn word_list(&mut self, list: &str) -> &mut MockBackend {
+        for line in list.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (word, suggestions) = match line.find(':') {
+                Some(i) => (&line[..i], &line[i + 1..]),
+                None => (line, ""),
+            };
+            let suggestions: Vec<_> = suggestions.split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .collect();
+            self.misspelled(word.trim(), &suggestions);
+        }
+        self
+    }
+
+    /// Waits `latency` milliseconds before each answer
+    pub fn latency(&mut self, latency: u64) -> &mut MockBackend {
+        self.latency = Duration::from_millis(latency);
+        self
+    }
+
+    /// Crashes instead of giving the `n+1`-th answer
+    pub fn crash_after(&mut self, n: usize) -> &mut MockBackend {
+        self.crash_after = Some(n);
+        self
+    }
+
+    /// Gives a malformed answer instead of the `n+1`-th answer, and all the following ones
+    pub fn malformed_after(&mut self, n: usize) -> &mut MockBackend {
+        self.malformed_after = Some(n);
+        self
+    }
+
+    /// Sets the file where words added with `*` are saved, and read at startup
+    pub fn personal_dictionary<P: AsRef<Path>>(&mut self, path: P) -> &mut MockBackend {
+        self.personal = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Answers the commands read from `input` until it is closed
+    ///
+    /// A simulated crash returns an error.
+    pub fn run<R: BufRead, W: Write>(&self, input: R, mut output: W) -> io::Result<()> {
+        let mut session = Session {
+            words: HashSet::new(),
+            unsaved: vec!(),
+            terse: false,
+            answers: 0,
+        };
+        if let Some(ref path) = self.personal {
+            if let Ok(words) = fs::read_to_string(path) {
+                session.words.extend(words.split_whitespace().map(|word| word.to_owned()));
+            }
+        }
+
+        writeln!(output, "{}", self.banner)?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            let mut chars = line.chars();
+            match chars.next() {
+                Some('*') | Some('@') => self.add_word(&mut session, chars.as_str(), line.starts_with('*')),
+                Some('&') => self.add_word(&mut session, &chars.as_str().to_lowercase(), true),
+                Some('#') => self.save(&mut session)?,
+                Some('!') => session.terse = true,
+                Some('%') => session.terse = false,
+                Some('+') | Some('-') | Some('~') | Some('`') => (),
+                Some('^') => self.answer(&mut session, chars.as_str(), 1, &mut output)?,
+                _ => self.answer(&mut session, &line, 0, &mut output)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a word to the session, and to the personal dictionary if `persistent` is set
+    fn add_word(&self, session: &mut Session, word: &str, persistent: bool) {
+        if word.chars().any(|c| c.is_ascii_digit()) {
+            return;
+        }
+        if persistent {
+            session.unsaved.push(word.to_owned());
+        }
+        session.words.insert(word.to_owned());
+    }
+
+    /// Saves the words added to the personal dictionary
+    fn save(&self, session: &mut Session) -> io::Result<()> {
+        if let Some(ref path) = self.personal {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            for word in session.unsaved.drain(..) {
+                writeln!(file, "{}", word)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answers a line to check, whose position in the input line is `offset`
+    fn answer<W: Write>(&self, session: &mut Session, text: &str, offset: usize, output: &mut W)
+                        -> io::Result<()> {
+        thread::sleep(self.latency);
+        if self.crash_after.map(|n| session.answers >= n).unwrap_or(false) {
+            return Err(io::Error::new(io::ErrorKind::Other, "simulated crash"));
+        }
+        if self.malformed_after.map(|n| session.answers >= n).unwrap_or(false) {
+            write!(output, "? this is not a valid answer\n\n")?;
+        } else {
+            for (start, word) in words(text) {
+                match self.misspelled.get(word) {
+                    Some(_) if session.words.contains(word) => if !session.terse {
+                        writeln!(output, "*")?
+                    },
+                    Some(suggestions) if suggestions.is_empty() => {
+                        writeln!(output, "# {} {}", word, start + offset)?
+                    },
+                    Some(suggestions) => {
+                        writeln!(output, "& {} {} {}: {}", word, suggestions.len(), start + offset,
+                                 suggestions.join(", "))?
+                    },
+                    None => if !session.terse {
+                        writeln!(output, "*")?
+                    },
+                }
+            }
+            writeln!(output)?;
+        }
+        session.answers += 1;
+        output.flush()
+    }
+}
+
+/// Splits a line into words (alphanumeric characters), along with their byte offset
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut start = None;
+    text.char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+        .filter_map(move |(i, c)| {
+            if c.is_alphanumeric() {
+                if start.is_none() {
+                    start = Some(i);
+                }
+                None
+            } else {
+                start.take().map(|start| (start, &text[start..i]))
+            }
+        })
+}
+
+
+#[test]
+fn mock_session() {
+    let mut backend = MockBackend::new();
+    backend.word_list("# comment\nthit: this, that\nrustacean\n");
+    let input = "^thit rustacean café\n@rustacean\n!\n^rustacean ok\n%\n";
+    let mut output = vec!();
+    backend.run(input.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    // In terse mode, the answer to a correct line is only an empty line
+    assert_eq!(output.split_once('\n').unwrap().1, "& thit 2 1: this, that\n# rustacean 6\n*\n\n\n");
+
+    backend.malformed_after(0);
+    let mut output = vec!();
+    backend.run(&b"^thit\n"[..], &mut output).unwrap();
+    assert!(String::from_utf8(output).unwrap().ends_with("? this is not a valid answer\n\n"));
+    assert!(backend.crash_after(0).run(&b"^thit\n"[..], &mut vec!()).is_err());
+}
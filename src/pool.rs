@@ -0,0 +1,368 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::HashMap;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+use crate::backend::SpellBackend;
+use crate::error::{Result, Error};
+use crate::protocol;
+use crate::spell_checker::SpellChecker;
+use crate::spell_launcher::SpellLauncher;
+
+/// Creates the checkers of a pool
+type Launch<B> = Box<dyn Fn() -> Result<B> + Send + Sync>;
+
+/// Creates the checkers of a pool, given the name of the dictionary
+type LaunchDictionary<B> = Arc<dyn Fn(&str) -> Result<B> + Send + Sync>;
+
+/// A pool of spell checkers, that can be shared between threads.
+///
+/// Since `SpellChecker` methods take `&mut self`, a single checker can only
+/// check one line at a time. A `SpellCheckerPool` runs several processes
+/// launched with the same `SpellLauncher` configuration, and hands them out
+/// with `get`, that returns a guard giving back the checker to the pool when
+/// it is dropped.
+///
+/// Checkers whose process crashed or timed out are not given back to the pool,
+/// and a new one is launched when needed.
+///
+/// Pools can also hold any other `SpellBackend`, created by a function given
+/// to `with_launcher`.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use std::thread;
+/// use ispell::{SpellLauncher, SpellCheckerPool};
+///
+/// let pool = Arc::new(SpellCheckerPool::new(&SpellLauncher::new(), 2).unwrap());
+/// pool.add_word("rustaholic").unwrap();
+///
+/// let handles: Vec<_> = (0..4).map(|_| {
+///     let pool = pool.clone();
+///     thread::spawn(move || {
+///         let mut checker = pool.get().unwrap();
+///         checker.check("Does thit rustaholic contain any erors?").unwrap()
+///     })
+/// }).collect();
+///
+/// for handle in handles {
+///     assert_eq!(handle.join().unwrap().len(), 2);
+/// }
+/// ```
+pub struct SpellCheckerPool<B = SpellChecker> {
+    launch: Launch<B>,
+    size: usize,
+    state: Mutex<PoolState<B>>,
+    available: Condvar,
+}
+
+/// Mutable state of a pool
+struct PoolState<B> {
+    /// Checkers that are not currently used
+    idle: Vec<Member<B>>,
+    /// Number of running checkers, either idle or in use
+    running: usize,
+    /// Words added to the session with `SpellCheckerPool::add_word`
+    words: Vec<String>,
+}
+
+/// A checker of the pool
+struct Member<B> {
+    checker: B,
+    /// Number of session words that have been added to this checker
+    words: usize,
+}
+
+impl SpellCheckerPool {
+    /// Creates a new pool running `size` spell checkers launched by `launcher`
+    ///
+    /// # Returns
+    ///
+    /// An error if `size` is zero, or if one of the spell checkers could not be launched.
+    pub fn new(launcher: &SpellLauncher, size: usize) -> Result<SpellCheckerPool> {
+        let launcher = launcher.clone();
+        SpellCheckerPool::with_launcher(size, move || launcher.launch())
+    }
+}
+
+impl<B: SpellBackend> SpellCheckerPool<B> {
+    /// Creates a new pool of `size` spell checkers created by `launch`
+    ///
+    /// `launch` is also called later, to replace checkers that are not alive anymore.
+    ///
+    /// # Returns
+    ///
+    /// An error if `size` is zero, or if one of the spell checkers could not be created.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ispell::{SpellCheckerPool, HunspellDictionary, SpellBackend};
+    /// let pool = SpellCheckerPool::with_launcher(2, || {
+    ///     HunspellDictionary::new("", "2\nhello\nworld\n")
+    /// }).unwrap();
+    /// let mut checker = pool.get().unwrap();
+    /// assert_eq!(checker.check("hello wrold").unwrap().len(), 1);
+    /// ```
+    pub fn with_launcher<F>(size: usize, launch: F) -> Result<SpellCheckerPool<B>>
+        where F: Fn() -> Result<B> + Send + Sync + 'static {
+        if size == 0 {
+            return Err(Error::new("the size of a pool must be at least one"));
+        }
+        let mut idle = Vec::with_capacity(size);
+        for _ in 0..size {
+            idle.push(Member {
+                checker: launch()?,
+                words: 0,
+            });
+        }
+        Ok(SpellCheckerPool {
+            launch: Box::new(launch),
+            size,
+            state: Mutex::new(PoolState {
+                idle,
+                running: size,
+                words: vec!(),
+            }),
+            available: Condvar::new(),
+        })
+    }
+
+    /// Returns the maximal number of checkers in this pool
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Gets a spell checker, waiting for one to be available if they are all in use
+    ///
+    /// # Returns
+    ///
+    /// An error if a new checker had to be launched (to replace one that crashed) and
+    /// this failed.
+    pub fn get(&self) -> Result<PooledSpellChecker<'_, B>> {
+        let mut state = self.lock();
+        loop {
+            if let Some((member, words)) = self.reserve(&mut state) {
+                drop(state);
+                return self.prepare(member, words);
+            }
+            state = self.available.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Gets a spell checker if one is available, without waiting
+    pub fn try_get(&self) -> Result<Option<PooledSpellChecker<'_, B>>> {
+        let reserved = self.reserve(&mut self.lock());
+        match reserved {
+            Some((member, words)) => self.prepare(member, words).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Adds a word to the current session of all the checkers of this pool
+    ///
+    /// The word is added to checkers when they are taken from the pool, so it is also
+    /// added to the ones that are launched later to replace crashed ones.
+    ///
+    /// # Returns
+    ///
+    /// An error if `word` is empty or contains multiple words (i.e., spaces).
+    pub fn add_word(&self, word: &str) -> Result<()> {
+        protocol::validate_word(word)?;
+        self.lock().words.push(word.to_owned());
+        Ok(())
+    }
+
+    /// Locks the state of the pool
+    ///
+    /// Checkers are launched and used without holding the lock, and the state is
+    /// never left inconsistent, so it can still be used if a thread panicked.
+    fn lock(&self) -> MutexGuard<'_, PoolState<B>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Reserves a place for a checker, if there is one
+    ///
+    /// Returns either an idle checker or `None` if a new one must be launched, along
+    /// with the session words it doesn't know yet.
+    fn reserve(&self, state: &mut PoolState<B>) -> Option<(Option<Member<B>>, Vec<String>)> {
+        while let Some(mut member) = state.idle.pop() {
+            if member.checker.is_alive() {
+                let words = state.words[member.words..].to_vec();
+                return Some((Some(member), words));
+            }
+            state.running -= 1;
+        }
+        if state.running < self.size {
+            state.running += 1;
+            return Some((None, state.words.clone()));
+        }
+        None
+    }
+
+    /// Launches the checker if needed and adds the session words it doesn't know yet,
+    /// without holding the lock
+    ///
+    /// If this fails (or panics), the place that was reserved is released.
+    fn prepare(&self, member: Option<Member<B>>, words: Vec<String>) -> Result<PooledSpellChecker<'_, B>> {
+        let reservation = Reservation { pool: self };
+        let mut member = match member {
+            Some(member) => member,
+            None => Member {
+                checker: (self.launch)()?,
+                words: 0,
+            },
+        };
+        for word in &words {
+            member.checker.add_word(word)?;
+        }
+        member.words += words.len();
+        mem::forget(reservation);
+        Ok(PooledSpellChecker {
+            pool: self,
+            member: Some(member),
+        })
+    }
+
+    /// Releases the place of a checker that is not running anymore
+    fn release(&self) {
+        self.lock().running -= 1;
+        self.available.notify_one();
+    }
+
+    /// Gives back a checker to the pool
+    fn give_back(&self, mut member: Member<B>) {
+        let mut state = self.lock();
+        if member.checker.is_alive() {
+            state.idle.push(member);
+        } else {
+            state.running -= 1;
+        }
+        self.available.notify_one();
+    }
+}
+
+/// A place reserved in a pool, released when dropped unless it is forgotten
+struct Reservation<'a, B: SpellBackend> {
+    pool: &'a SpellCheckerPool<B>,
+}
+
+impl<'a, B: SpellBackend> Drop for Reservation<'a, B> {
+    fn drop(&mut self) {
+        self.pool.release();
+    }
+}
+
+/// A spell checker taken from a `SpellCheckerPool`
+///
+/// It dereferences to `SpellChecker` (or to the backend of the pool), and is given
+/// back to the pool when dropped.
+///
+/// Note that words added with `SpellChecker::add_word` only affect this checker;
+/// use `SpellCheckerPool::add_word` to add a word to all the checkers of the pool.
+pub struct PooledSpellChecker<'a, B: SpellBackend = SpellChecker> {
+    pool: &'a SpellCheckerPool<B>,
+    member: Option<Member<B>>,
+}
+
+impl<'a, B: SpellBackend> Deref for PooledSpellChecker<'a, B> {
+    type Target = B;
+
+    fn deref(&self) -> &B {
+        &self.member.as_ref().unwrap().checker
+    }
+}
+
+impl<'a, B: SpellBackend> DerefMut for PooledSpellChecker<'a, B> {
+    fn deref_mut(&mut self) -> &mut B {
+        &mut self.member.as_mut().unwrap().checker
+    }
+}
+
+impl<'a, B: SpellBackend> Drop for PooledSpellChecker<'a, B> {
+    fn drop(&mut self) {
+        if let Some(member) = self.member.take() {
+            self.pool.give_back(member);
+        }
+    }
+}
+
+/// A set of `SpellCheckerPool`s, one for each dictionary
+///
+/// Pools are created the first time a dictionary is requested, using the same
+/// `SpellLauncher` configuration except for the dictionary, or the function given
+/// to `with_launcher`.
+///
+/// # Example
+///
+/// ```no_run
+/// use ispell::{SpellLauncher, LanguagePools};
+///
+/// let pools = LanguagePools::new(&SpellLauncher::new(), 2);
+/// let pool = pools.pool("american").unwrap();
+/// let mut checker = pool.get().unwrap();
+/// let errors = checker.check("Does thit message contain any erors?").unwrap();
+/// assert_eq!(errors.len(), 2);
+/// ```
+pub struct LanguagePools<B = SpellChecker> {
+    launch: LaunchDictionary<B>,
+    size: usize,
+    pools: Mutex<HashMap<String, Arc<SpellCheckerPool<B>>>>,
+}
+
+impl LanguagePools {
+    /// Creates a new set of pools, each of them running up to `size` checkers
+    pub fn new(launcher: &SpellLauncher, size: usize) -> LanguagePools {
+        let launcher = launcher.clone();
+        LanguagePools::with_launcher(size, move |dictionary| launcher.clone().dictionary(dictionary).launch())
+    }
+}
+
+impl<B: SpellBackend + 'static> LanguagePools<B> {
+    /// Creates a new set of pools, each of them running up to `size` checkers created by
+    /// `launch`, which is given the name of the dictionary
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::{LanguagePools, HunspellDictionary};
+    /// let pools = LanguagePools::with_launcher(2, HunspellDictionary::load);
+    /// let pool = pools.pool("en_US").unwrap();
+    /// ```
+    pub fn with_launcher<F>(size: usize, launch: F) -> LanguagePools<B>
+        where F: Fn(&str) -> Result<B> + Send + Sync + 'static {
+        LanguagePools {
+            launch: Arc::new(launch),
+            size,
+            pools: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the pool for this dictionary, creating it if needed
+    ///
+    /// See `SpellLauncher::dictionary` for the naming of dictionaries.
+    pub fn pool(&self, dictionary: &str) -> Result<Arc<SpellCheckerPool<B>>> {
+        if let Some(pool) = self.lock().get(dictionary) {
+            return Ok(pool.clone());
+        }
+        // The checkers are launched without holding the lock, so other dictionaries
+        // can be used meanwhile; if another thread created this pool in the meantime,
+        // it is kept and this one is dropped.
+        let launch = self.launch.clone();
+        let name = dictionary.to_owned();
+        let pool = Arc::new(SpellCheckerPool::with_launcher(self.size, move || launch(&name))?);
+        Ok(self.lock().entry(dictionary.to_owned()).or_insert(pool).clone())
+    }
+
+    /// Locks the map of pools
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Arc<SpellCheckerPool<B>>>> {
+        self.pools.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
@@ -0,0 +1,123 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fmt;
+use std::ops::Add;
+
+/// Unit in which a spell checker reports the position of a word
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Unit {
+    /// Number of bytes (in UTF-8)
+    Byte,
+    /// Number of unicode characters
+    Char,
+}
+
+/// The position of a word in a string.
+///
+/// `ispell`, `aspell` and `hunspell` don't count the same way (some count bytes, others
+/// characters), so positions are normalized and can be expressed in three units:
+///
+/// * `byte` gives the offset in bytes, which is what you need to slice a Rust string;
+/// * `char` gives the number of unicode characters;
+/// * `utf16` gives the number of UTF-16 code units, which is what most editors
+///   (and the Language Server Protocol) use.
+///
+/// When displayed, a `Position` shows the number of characters.
+///
+/// # Example
+///
+/// ```no_run
+/// use ispell::SpellLauncher;
+/// let mut checker = SpellLauncher::new().launch().unwrap();
+/// let text = "Café thit";
+/// let errors = checker.check(text).unwrap();
+/// let position = errors[0].position;
+/// assert_eq!(position.char(), 5);
+/// assert_eq!(position.byte(), 6);
+/// assert_eq!(&text[position.byte()..], "thit");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    byte: usize,
+    char: usize,
+    utf16: usize,
+}
+
+impl Position {
+    /// Offset in bytes
+    pub fn byte(&self) -> usize {
+        self.byte
+    }
+
+    /// Offset in unicode characters
+    pub fn char(&self) -> usize {
+        self.char
+    }
+
+    /// Offset in UTF-16 code units
+    pub fn utf16(&self) -> usize {
+        self.utf16
+    }
+
+    /// Returns the position at the end of `text`
+    pub(crate) fn end_of(text: &str) -> Position {
+        Position {
+            byte: text.len(),
+            char: text.chars().count(),
+            utf16: text.encode_utf16().count(),
+        }
+    }
+
+    /// Converts an offset in the given unit into a `Position` in `text`
+    ///
+    /// Returns `None` if the offset is out of bounds or not on a character boundary.
+    pub(crate) fn from_offset(text: &str, offset: usize, unit: Unit) -> Option<Position> {
+        let byte = match unit {
+            Unit::Byte => offset,
+            Unit::Char => text.char_indices()
+                .map(|(i, _)| i)
+                .chain(Some(text.len()))
+                .nth(offset)?,
+        };
+        if byte > text.len() || !text.is_char_boundary(byte) {
+            return None;
+        }
+        Some(Position::end_of(&text[..byte]))
+    }
+}
+
+impl Add for Position {
+    type Output = Position;
+
+    fn add(self, other: Position) -> Position {
+        Position {
+            byte: self.byte + other.byte,
+            char: self.char + other.char,
+            utf16: self.utf16 + other.utf16,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.char)
+    }
+}
+
+
+#[test]
+fn position_units() {
+    let text = "ça 日本 😀 end";
+    let pos = Position::from_offset(text, 6, Unit::Char).unwrap();
+    assert_eq!(&text[pos.byte()..], "😀 end");
+    assert_eq!(pos, Position::from_offset(text, 11, Unit::Byte).unwrap());
+    assert_eq!(pos.utf16(), 6);
+    let end = Position::from_offset(text, 11, Unit::Char).unwrap();
+    assert_eq!(end.utf16(), 12);
+    assert_eq!(&text[end.byte()..], "");
+    assert!(Position::from_offset(text, 1, Unit::Byte).is_none());
+    assert!(Position::from_offset(text, 12, Unit::Char).is_none());
+}
@@ -0,0 +1,242 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Parsing of the `ispell -a` pipe protocol, shared by the blocking and async checkers
+
+use crate::backend_info::Flavour;
+use crate::error::{Result, Error};
+use crate::ispell_result::{IspellResult, IspellError};
+use crate::position::{Position, Unit};
+use crate::spell_launcher::Format;
+
+/// Returns true if `output` is a complete answer from ispell
+///
+/// That is, either the version banner, or lines terminated by an empty line. In terse
+/// mode, the answer to a line without errors is only this empty line.
+pub(crate) fn is_complete(output: &[u8]) -> bool {
+    output.ends_with(b"\n\n") || output == b"\n" || output.starts_with(b"@")
+}
+
+/// Checks that a word can be sent to the spell checker, to be added to a dictionary
+pub(crate) fn validate_word(word: &str) -> Result<()> {
+    if word.is_empty() {
+        return Err(Error::invalid_word("empty word"));
+    }
+    if word.contains(|c:char| c.is_whitespace()) {
+        return Err(Error::invalid_word(format!("word '{}' contains space(s)",
+                                               word)));
+    }
+    if word.contains(char::is_control) {
+        return Err(Error::invalid_word(format!("word '{}' contains control character(s)",
+                                               word)));
+    }
+    Ok(())
+}
+
+/// Tells whether a word would be split into several words by the spell checker,
+/// given the answer to the line containing only this word
+///
+/// Returns the reason why it can't be added to a dictionary, if it is.
+pub(crate) fn split_word(answer: &str, word: &str, unit: Unit) -> Result<Option<String>> {
+    if parse_output(answer, word, unit)?.len() > 1 {
+        Ok(Some(format!("word '{}' is not a single word for the spell checker", word)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Tells whether a word was accepted by the spell checker after it was added, given
+/// the answer to the line containing only this word
+///
+/// Returns the reason why it was rejected, if it was. Aspell reports invalid words
+/// with a line starting with `Error:`; other programs silently ignore them, so the
+/// word is still misspelled.
+pub(crate) fn rejection(answer: &str, word: &str, unit: Unit) -> Result<Option<String>> {
+    if let Some(line) = answer.lines().find(|line| line.starts_with("Error:")) {
+        return Ok(Some(format!("word '{}' was rejected: {}", word, line["Error:".len()..].trim())));
+    }
+    let misspelled = parse_output(answer, word, unit)?
+        .iter()
+        .any(|result| !matches!(*result, IspellResult::Ok | IspellResult::Root(_) | IspellResult::Compound));
+    if misspelled {
+        Ok(Some(format!("word '{}' was not accepted by the spell checker", word)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Returns the command that switches a running program of the given flavour to `format`
+///
+/// The protocol only has `+` (TeX mode) and `-`, that goes back to the format the
+/// program was launched with (`launched`, or if it is `None` its default parser, which
+/// is nroff for ispell and plain text for the others).
+pub(crate) fn format_command(format: Format, flavour: Flavour, launched: Option<Format>)
+                             -> Result<&'static [u8]> {
+    match (format, flavour) {
+        (Format::Tex, _) => Ok(b"+\n"),
+        (Format::Nroff, Flavour::Ispell) if launched.unwrap_or(Format::Nroff) == Format::Nroff => Ok(b"-\n"),
+        (format, Flavour::Aspell) | (format, Flavour::Hunspell) if launched.unwrap_or(Format::Plain) == format => {
+            Ok(b"-\n")
+        },
+        (format, flavour) => Err(Error::unsupported(format!("{} can't switch to the {:?} format once running",
+                                                            flavour, format))),
+    }
+}
+
+/// Parses the answer of ispell to the line `text`
+///
+/// `unit` is the unit in which the spell checker is expected to count positions.
+pub(crate) fn parse_output(s: &str, text: &str, unit: Unit) -> Result<Vec<IspellResult>> {
+    let mut output = Vec::new();
+
+    for line in s.lines() {
+        if line.is_empty() {
+            break;
+        }
+        let first = line.chars().next().unwrap();
+        match first {
+            '*' => output.push(IspellResult::Ok),
+            '-' => output.push(IspellResult::Compound),
+            '+' => {
+                let words:Vec<_> = line.split_whitespace().collect();
+                if words.len() != 2 {
+                    return Err(Error::protocol_line(format!("'root' line ill-formatted: {}", line), line));
+                }
+                output.push(IspellResult::Root(words[1].to_owned()));
+            },
+            '#' => {
+                let error = get_ispell_error(line, 3, text, unit)?;
+                output.push(IspellResult::None(error));
+            },
+            '&' | '?' => {
+                let parts: Vec<_> = line.split(':').collect();
+                if parts.len() != 2 {
+                    return Err(Error::protocol_line(format!("unexpected output from ispell: {}", line), line));
+                }
+                let mut error = get_ispell_error(parts[0], 4, text, unit)?;
+                let suggestions: Vec<_> = parts[1].split(',')
+                    .map(|s| s.trim().to_owned())
+                    .collect();
+                error.suggestions = suggestions;
+                if first == '&' {
+                    output.push(IspellResult::Miss(error));
+                } else {
+                    output.push(IspellResult::Guess(error));
+                }
+            },
+            _ => return Err(Error::protocol_line(format!("unexpected output: {}", line), line)),
+        }
+    }
+
+    Ok(output)
+}
+
+/// Transforms a string looking like "# unkwnown POS' or '& unknown n POS' to an IspellError
+///
+/// `text` is the line that was checked and `unit` the unit in which the spell checker
+/// is expected to count.
+fn get_ispell_error(input: &str, n: usize, text: &str, unit: Unit) -> Result<IspellError> {
+    let words: Vec<_> = input.split_whitespace().collect();
+    if words.len() != n {
+        return Err(Error::protocol_line(format!("unexpected result: {}", input), input));
+    }
+    let misspelled = words[1].to_owned();
+    let offset: usize = words[n - 1].parse()
+        .map_err(|_| Error::protocol_line(format!("could not parse '{}' as an int", words[n - 1]), input))?;
+    // remove the '^' character we add for escaping
+    let position = offset.checked_sub(1)
+        .and_then(|offset| locate(text, &misspelled, offset, unit))
+        .ok_or_else(|| Error::protocol_line(format!("invalid position for '{}': {}", misspelled, offset), input))?;
+    Ok(IspellError {
+        misspelled,
+        position,
+        suggestions: vec!(),
+    })
+}
+
+/// Converts an offset returned by the spell checker into a `Position` in `text`
+///
+/// The unit actually used by a program can depend on its version or on the encoding, so
+/// if `word` isn't found at `offset` in the expected unit, the other one is tried.
+fn locate(text: &str, word: &str, offset: usize, unit: Unit) -> Option<Position> {
+    let other = match unit {
+        Unit::Byte => Unit::Char,
+        Unit::Char => Unit::Byte,
+    };
+    let candidates: Vec<_> = [unit, other].iter()
+        .filter_map(|&unit| Position::from_offset(text, offset, unit))
+        .collect();
+    candidates.iter()
+        .find(|position| text[position.byte()..].starts_with(word))
+        .or_else(|| candidates.first())
+        .cloned()
+}
+
+
+#[test]
+fn error_positions() {
+    let text = "Café thit";
+    let error = get_ispell_error("# thit 6", 3, text, Unit::Char).unwrap();
+    assert_eq!(error.position.byte(), 6);
+    // "thit" isn't at char 6, so this is understood as a number of bytes
+    let error = get_ispell_error("# thit 7", 3, text, Unit::Char).unwrap();
+    assert_eq!(error.position.char(), 5);
+    assert!(get_ispell_error("# thit 42", 3, text, Unit::Byte).is_err());
+}
+
+#[test]
+fn parse_answer() {
+    let text = "Testing iff if it wrks";
+    let results = parse_output("*\n& iff 2 9: if, riff\n*\n*\n# wrks 19\n\n", text, Unit::Char).unwrap();
+    assert_eq!(results.len(), 5);
+    match results[1] {
+        IspellResult::Miss(ref error) => {
+            assert_eq!(&error.misspelled, "iff");
+            assert_eq!(error.position.char(), 8);
+            assert_eq!(error.suggestions, vec!("if", "riff"));
+        },
+        _ => panic!("unexpected result: {:?}", results[1]),
+    }
+    assert!(parse_output("unexpected\n\n", text, Unit::Char).is_err());
+}
+
+#[test]
+fn terse_answers() {
+    assert!(is_complete(b"\n"));
+    assert!(!is_complete(b"# wrks 19\n"));
+    assert!(is_complete(b"# wrks 19\n\n"));
+    assert!(parse_output("\n", "Testing if it works", Unit::Char).unwrap().is_empty());
+}
+
+#[test]
+fn format_commands() {
+    assert_eq!(format_command(Format::Tex, Flavour::Aspell, None).unwrap(), b"+\n");
+    assert_eq!(format_command(Format::Plain, Flavour::Hunspell, None).unwrap(), b"-\n");
+    assert_eq!(format_command(Format::Nroff, Flavour::Ispell, None).unwrap(), b"-\n");
+    assert_eq!(format_command(Format::Html, Flavour::Aspell, Some(Format::Html)).unwrap(), b"-\n");
+    assert!(format_command(Format::Html, Flavour::Hunspell, None).is_err());
+    assert!(format_command(Format::Plain, Flavour::Ispell, None).is_err());
+    // `-` would go back to HTML
+    let err = format_command(Format::Plain, Flavour::Aspell, Some(Format::Html)).unwrap_err();
+    assert_eq!(err.kind(), &crate::ErrorKind::Unsupported);
+}
+
+#[test]
+fn rejected_words() {
+    assert!(validate_word("rustacean").is_ok());
+    assert!(validate_word("").is_err());
+    assert!(validate_word("two words").is_err());
+    assert!(validate_word("bell\u{7}").is_err());
+
+    assert_eq!(rejection("*\n\n", "rustacean", Unit::Byte).unwrap(), None);
+    assert_eq!(rejection("\n", "rustacean", Unit::Byte).unwrap(), None);
+    assert_eq!(rejection("# word2 1\n\n", "word2", Unit::Byte).unwrap().unwrap(),
+               "word 'word2' was not accepted by the spell checker");
+    assert_eq!(split_word("*\n*\n\n", "foo-bar", Unit::Byte).unwrap().unwrap(),
+               "word 'foo-bar' is not a single word for the spell checker");
+    assert_eq!(split_word("& foobar 1 1: foo bar\n\n", "foobar", Unit::Byte).unwrap(), None);
+    let answer = "Error: The word \"w2\" is invalid.\n# w2 1\n\n";
+    assert_eq!(rejection(answer, "w2", Unit::Byte).unwrap().unwrap(),
+               "word 'w2' was rejected: The word \"w2\" is invalid.");
+}
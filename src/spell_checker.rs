@@ -3,14 +3,21 @@
 // this file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use std::process::{Child, ChildStdin};
-use std::io::Write;
-use std::time::Duration;
+use std::io::{BufRead, Write};
+use std::time::{Duration, Instant};
 use std::thread;
-use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, TryRecvError};
 
-use error::{Result, Error};
-use ispell_result::{IspellResult, IspellError};
-use async_reader::AsyncReader;
+use crate::backend_info::BackendInfo;
+use crate::batch::{CheckLines, CheckReader};
+use crate::error::{Result, Error, ErrorKind};
+use crate::ispell_result::{IspellResult, IspellError, DocumentError, RejectedWord};
+use crate::protocol;
+use crate::async_reader::AsyncReader;
+use crate::stderr_buffer::StderrBuffer;
+use crate::transcript::{Transcript, Direction};
+use crate::position::{Position, Unit};
+use crate::spell_launcher::{SpellLauncher, Mode, Format};
 
 /// Spell Checker
 ///
@@ -18,7 +25,7 @@ use async_reader::AsyncReader;
 ///
 /// # Example
 ///
-/// ```
+/// ```no_run
 /// use ispell::SpellLauncher;
 /// let mut checker = SpellLauncher::new().launch().unwrap();
 /// let errors = checker.check("This should not contain any error").unwrap();
@@ -28,14 +35,39 @@ pub struct SpellChecker {
     ispell: Child,
     stdin: ChildStdin,
     receiver: Receiver<Result<String>>,
+    stderr: StderrBuffer,
+    backend_info: BackendInfo,
     timeout: Duration,
+    unit: Unit,
+    /// Whether the terse mode is enabled
+    terse: bool,
+    /// Set when the process stopped answering
+    pub(crate) broken: bool,
+    /// Configuration used to restart the process
+    launcher: Option<SpellLauncher>,
+    /// The format set when the process was launched, if any
+    launch_format: Option<Format>,
+    /// Words added to the session, that must be added again after a restart
+    words: Vec<String>,
+    /// Words added to the personal dictionary that haven't been saved yet
+    unsaved: Vec<String>,
+    restarts: u32,
+    transcript: Option<Transcript>,
     _child: thread::JoinHandle<()>,
 }
 
 impl SpellChecker {
     /// Creates a new spell checker from a running process
     #[doc(hidden)]
-    pub fn new(mut process: Child, timeout: u64) -> Result<SpellChecker> {
+    pub fn new(process: Child, timeout: u64) -> Result<SpellChecker> {
+        SpellChecker::with_unit(process, timeout, Unit::Char, None)
+    }
+
+    /// Creates a new spell checker from a running process, that reports positions in `unit`
+    ///
+    /// If `transcript` is set, everything exchanged with the process is recorded in it.
+    pub(crate) fn with_unit(mut process: Child, timeout: u64, unit: Unit, transcript: Option<Transcript>)
+                            -> Result<SpellChecker> {
         let stdin = if let Some(stdin) = process.stdin.take() {
             stdin
         } else {
@@ -45,20 +77,40 @@ impl SpellChecker {
         let stdout = if let Some(stdout) = process.stdout.take() {
             stdout
         } else {
-            return Err(Error::process("could not access stdin of spawned process"));
+            return Err(Error::process("could not access stdout of spawned process"));
+        };
+
+        let stderr = match process.stderr.take() {
+            Some(stderr) => StderrBuffer::spawn(stderr),
+            None => {
+                let buffer = StderrBuffer::default();
+                buffer.close();
+                buffer
+            },
         };
 
         let (sender, receiver) = channel();
-        let mut reader = AsyncReader::new(stdout, sender);
+        let mut reader = AsyncReader::new(stdout, sender, transcript.clone());
         let child = thread::spawn(move || {
             reader.read_loop();
         });
         
         let mut checker = SpellChecker {
             ispell: process,
-            stdin: stdin,
+            stdin,
             timeout: Duration::from_millis(timeout),
-            receiver: receiver,
+            unit,
+            terse: false,
+            receiver,
+            stderr,
+            backend_info: BackendInfo::parse(""),
+            broken: false,
+            launcher: None,
+            launch_format: None,
+            words: vec!(),
+            unsaved: vec!(),
+            restarts: 0,
+            transcript,
             _child: child,
         };
 
@@ -66,27 +118,183 @@ impl SpellChecker {
         //       checker.write_str("")?;
         let s = checker.read_str()?;
         match s.chars().next() {
-            Some('@') => Ok(checker),
-            _ => Err(Error::protocol(format!("First line of ispell output doesn't start with '@', aborting")))
+            Some('@') => {
+                checker.backend_info = BackendInfo::parse(&s);
+                Ok(checker)
+            },
+            _ => Err(Error::protocol_line("First line of ispell output doesn't start with '@', aborting",
+                                          s.lines().next().unwrap_or(""))
+                     .with_stderr(checker.stderr.text()))
+        }
+    }
+
+    /// Returns the mode (i.e., the program) this checker was launched with
+    ///
+    /// This is useful when it was detected by `SpellLauncher::auto`. It returns `None`
+    /// if a custom command was used, or if this checker wasn't created by a `SpellLauncher`.
+    pub fn mode(&self) -> Option<Mode> {
+        self.launcher.as_ref().and_then(|launcher| launcher.get_mode())
+    }
+
+    /// Returns informations about the spawned program, read from its version banner
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    /// let checker = SpellLauncher::new().launch().unwrap();
+    /// let info = checker.backend_info();
+    /// println!("{} (emulating ispell {:?})", info, info.ispell_version);
+    /// ```
+    pub fn backend_info(&self) -> &BackendInfo {
+        &self.backend_info
+    }
+
+    /// Returns the last lines the spawned process wrote to its stderr
+    ///
+    /// Only the last hundred lines are kept. Note that they are also attached to errors
+    /// caused by the process (see `Error::stderr`).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    /// let checker = SpellLauncher::new().launch().unwrap();
+    /// for line in checker.stderr() {
+    ///     println!("ispell said: {}", line);
+    /// }
+    /// ```
+    pub fn stderr(&self) -> Vec<String> {
+        self.stderr.lines()
+    }
+
+    /// Sets the configuration used to restart the process if needed
+    pub(crate) fn set_launcher(&mut self, launcher: SpellLauncher) {
+        self.launch_format = launcher.get_format();
+        self.launcher = Some(launcher);
+    }
+
+    /// Returns the transcript where everything exchanged with the spawned process is
+    /// recorded, if it was set with `SpellLauncher::transcript`
+    pub fn transcript(&self) -> Option<&Transcript> {
+        self.transcript.as_ref()
+    }
+
+    /// Returns the number of times the spawned process has been restarted
+    ///
+    /// See `SpellLauncher::auto_restart`.
+    pub fn restarts(&self) -> u32 {
+        self.restarts
+    }
+
+    /// Runs `f`, restarting the process and running it again if it failed because
+    /// the process crashed or timed out, and automatic restarts are enabled
+    pub(crate) fn retry<T, F>(&mut self, mut f: F) -> Result<T>
+        where F: FnMut(&mut SpellChecker) -> Result<T> {
+        let mut restarts = 0;
+        loop {
+            let mut err = match f(self) {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            let (max_restarts, backoff) = match self.launcher {
+                Some(ref launcher) => launcher.restart_policy(),
+                None => return Err(err),
+            };
+            if max_restarts == 0 || self.is_alive() {
+                return Err(err.with_restarts(restarts));
+            }
+            loop {
+                if restarts >= max_restarts {
+                    return Err(Error::restart(err, restarts));
+                }
+                thread::sleep(backoff * 2u32.saturating_pow(restarts));
+                restarts += 1;
+                match self.restart() {
+                    Ok(()) => break,
+                    Err(e) => err = e,
+                }
+            }
         }
     }
 
+    /// Replaces the spawned process by a new one
+    fn restart(&mut self) -> Result<()> {
+        let mut checker = match self.launcher {
+            Some(ref launcher) => launcher.launch()?,
+            None => return Err(Error::process("can't restart a process that wasn't spawned by SpellLauncher")),
+        };
+        for word in &self.words {
+            checker.send(&[b"@", word.as_bytes(), b"\n"])?;
+        }
+        for word in &self.unsaved {
+            checker.send(&[b"*", word.as_bytes(), b"\n"])?;
+        }
+        checker.words = std::mem::take(&mut self.words);
+        checker.unsaved = std::mem::take(&mut self.unsaved);
+        checker.restarts = self.restarts + 1;
+        *self = checker;
+        Ok(())
+    }
+
+    /// Returns `false` if the spawned process has exited or stopped answering in time
+    ///
+    /// A checker that isn't alive anymore can't be used and should be replaced by
+    /// a new one.
+    pub fn is_alive(&mut self) -> bool {
+        !self.broken && matches!(self.ispell.try_wait(), Ok(None))
+    }
+
     /// Reads the output from ispell
     fn read_str(&mut self) -> Result<String> {
-        match self.receiver.recv_timeout(self.timeout) {
+        self.read_str_until(None)
+    }
+
+    /// Reads the output from ispell, failing if it doesn't come before `deadline`
+    /// (in addition to the usual timeout)
+    pub(crate) fn read_str_until(&mut self, deadline: Option<Instant>) -> Result<String> {
+        let timeout = match deadline {
+            Some(deadline) => self.timeout.min(deadline.saturating_duration_since(Instant::now())),
+            None => self.timeout,
+        };
+        let result = match self.receiver.recv_timeout(timeout) {
+            Ok(Err(ref err)) if matches!(err.kind(), ErrorKind::ProcessExited { .. }) => Err(self.exited()),
             Ok(result) => result,
-            Err(_) => return Err(Error::process("timeout error: spawned process didn't respond in time, aborting")),
+            Err(RecvTimeoutError::Timeout) => Err(Error::timeout().with_stderr(self.stderr.text())),
+            Err(RecvTimeoutError::Disconnected) => Err(self.exited()),
+        };
+        if result.is_err() {
+            self.broken = true;
+        }
+        result
+    }
+
+    /// Returns an error telling that the spawned process closed its stdout
+    fn exited(&mut self) -> Error {
+        // The process may not have exited yet, so give it a little time
+        let mut status = None;
+        for _ in 0..10 {
+            if let Ok(Some(s)) = self.ispell.try_wait() {
+                status = Some(s);
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
         }
+        self.stderr.wait_closed(Duration::from_millis(100));
+        Error::process_exited(status, self.stderr.text())
     }
 
     /// Flushes the stdout of the spawned process, so we are sure we start
     /// reading an answer to what we just wrote
-    fn flush_stdout(&mut self) -> Result<()> {
+    pub(crate) fn flush_stdout(&mut self) -> Result<()> {
         loop {
             match self.receiver.try_recv() {
                 Ok(_) => continue,
                 Err(TryRecvError::Empty) => break,
-                Err(TryRecvError::Disconnected) => return Err(Error::process("spawned process closed its stdout early, aborting")),
+                Err(TryRecvError::Disconnected) => {
+                    self.broken = true;
+                    return Err(self.exited());
+                },
             }
         }
         Ok(())
@@ -97,11 +305,24 @@ impl SpellChecker {
         // First, clear ispell's stdout just in case
         self.flush_stdout()?;
         
-        self.stdin.write_all(b"^")?;
-        self.stdin.write_all(text.as_bytes())?;
-        self.stdin.write_all(b"\n")?;
-        self.stdin.flush()?;
-        Ok(())
+        // A line break would end the line early and mess up with the
+        // answers, so replace them by spaces (which keeps positions unchanged)
+        let text = text.replace(['\n', '\r'], " ");
+        self.send(&[b"^", text.as_bytes(), b"\n"])
+    }
+
+    /// Writes some bytes to ispell stdin and flushes it
+    pub(crate) fn send(&mut self, bytes: &[&[u8]]) -> Result<()> {
+        if let Some(ref transcript) = self.transcript {
+            transcript.record(Direction::Sent, &bytes.concat());
+        }
+        let result = bytes.iter()
+            .try_for_each(|bytes| self.stdin.write_all(bytes))
+            .and_then(|_| self.stdin.flush());
+        if result.is_err() {
+            self.broken = true;
+        }
+        Ok(result?)
     }
 
     /// Adds a word to your personal dictionary
@@ -110,12 +331,13 @@ impl SpellChecker {
     /// so it will be memorized next time you use i/a/hun/spell. If you only want to add the word to
     /// this current session, use `add_word`.
     ///
-    /// # Returns
+    /// To add many words, `add_words_to_dictionary` and `save_dictionary` are faster,
+    /// since the personal dictionary is only written once.
     ///
-    /// An error if connection to ispell failed, or `word` contains multiple words (i.e., spaces).
+    /// # Returns
     ///
-    /// Note that a successful return of this method doesn't mean that the word was successfully added, as
-    /// it is possible that it contains invalid character and ispell will reject it.
+    /// An error if connection to ispell failed, or if the word was rejected (see
+    /// `add_words_to_dictionary`), with the kind `ErrorKind::InvalidWord`.
     ///
     /// # Examples
     ///
@@ -158,18 +380,97 @@ impl SpellChecker {
     /// # }
     /// ```
     pub fn add_word_to_dictionary(&mut self, word: &str) -> Result<()> {
-        if word.contains(|c:char| c.is_whitespace()) {
-            return Err(Error::invalid_word(format!("word '{}' contains space(s)",
-                                                   word)));
+        if let Some(rejected) = self.add_words_to_dictionary([word])?.pop() {
+            return Err(Error::invalid_word(rejected.reason));
+        }
+        self.save_dictionary()
+    }
+
+    /// Adds words to your personal dictionary, without saving it
+    ///
+    /// Each word is checked after it is added, to make sure the spell checker accepted
+    /// it: words that are empty or contain spaces, that the program rejects (e.g. because
+    /// they contain characters that are not allowed by the dictionary) or that it splits
+    /// into several words are not added, and are returned along with the reason why.
+    ///
+    /// The words are only written to the personal dictionary by `save_dictionary`.
+    ///
+    /// # Returns
+    ///
+    /// The rejected words, or an error if the connection to ispell failed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ispell::SpellLauncher;
+    ///
+    /// let mut checker = SpellLauncher::new()
+    ///     .personal_dictionary("words.txt")
+    ///     .launch()
+    ///     .unwrap();
+    /// let rejected = checker.add_words_to_dictionary(["rustacean", "rustaholic", "not a word"])
+    ///     .unwrap();
+    /// assert_eq!(rejected.len(), 1);
+    /// println!("{}", rejected[0].reason);
+    /// checker.save_dictionary().unwrap();
+    /// ```
+    pub fn add_words_to_dictionary<I, S>(&mut self, words: I) -> Result<Vec<RejectedWord>>
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str> {
+        let mut rejected = vec!();
+        for word in words {
+            let word = word.as_ref();
+            let reason = match protocol::validate_word(word) {
+                Ok(()) => self.retry(|checker| {
+                    // In terse mode, correct words are not reported, so it is left
+                    // while checking the word to know how it is split
+                    let terse = checker.terse;
+                    if terse {
+                        checker.send(&[b"%\n"])?;
+                    }
+                    let reason = checker.add_checked_word(word);
+                    if terse {
+                        // Failing to restore it must not hide why the word couldn't be added
+                        let restored = checker.send(&[b"!\n"]);
+                        return reason.and_then(|reason| restored.map(|()| reason));
+                    }
+                    reason
+                })?,
+                Err(err) => Some(err.to_string()),
+            };
+            match reason {
+                Some(reason) => rejected.push(RejectedWord {
+                    word: word.to_owned(),
+                    reason,
+                }),
+                None => self.unsaved.push(word.to_owned()),
+            }
         }
-        self.stdin.write_all(b"*")?;
-        self.stdin.write_all(word.as_bytes())?;
-        self.stdin.write_all(b"\n")?;
-
-        // Save the dictionary
-        self.stdin.flush()?;
-        self.stdin.write_all(b"#\n")?;
-        self.stdin.flush()?;
+        Ok(rejected)
+    }
+
+    /// Adds a word to the personal dictionary if it is a single word, and checks it
+    /// was accepted
+    ///
+    /// Returns the reason why it was rejected, if it was.
+    fn add_checked_word(&mut self, word: &str) -> Result<Option<String>> {
+        self.write_str(word)?;
+        let answer = self.read_str()?;
+        if let Some(reason) = protocol::split_word(&answer, word, self.unit)? {
+            return Ok(Some(reason));
+        }
+        self.send(&[b"*", word.as_bytes(), b"\n"])?;
+        self.write_str(word)?;
+        let answer = self.read_str()?;
+        protocol::rejection(&answer, word, self.unit)
+    }
+
+    /// Saves your personal dictionary
+    ///
+    /// This writes the words added by `add_words_to_dictionary` to your words file.
+    pub fn save_dictionary(&mut self) -> Result<()> {
+        self.retry(|checker| checker.send(&[b"#\n"]))?;
+        self.unsaved.clear();
         Ok(())
     }
 
@@ -177,7 +478,7 @@ impl SpellChecker {
     ///
     /// Similar to `add_word_to_dictionary`, except `word` won't be memorized the next time you use i/a/hun/spell. 
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// use ispell::SpellLauncher;
     ///
     /// fn main() {
@@ -199,18 +500,54 @@ impl SpellChecker {
     /// }
     /// ```
     pub fn add_word(&mut self, word: &str) -> Result<()> {
-        if word.contains(|c:char| c.is_whitespace()) {
-            return Err(Error::invalid_word(format!("word '{}' contains space(s)",
-                                                   word)));
-        }
-        self.stdin.write_all(b"@")?;
-        self.stdin.write_all(word.as_bytes())?;
-        self.stdin.write_all(b"\n")?;
-        self.stdin.flush()?;
-        Ok(())
+        protocol::validate_word(word)?;
+        self.words.push(word.to_owned());
+        self.retry(|checker| checker.send(&[b"@", word.as_bytes(), b"\n"]))
     }
     
 
+    /// Changes the format of the text that is checked
+    ///
+    /// The ispell protocol only allows to switch to `Format::Tex`, and back to the
+    /// format set with `SpellLauncher::format`, or by default to the default format of
+    /// the program (`Format::Nroff` for `ispell`, `Format::Plain` for `aspell` and
+    /// `hunspell`). Other formats return an error of kind `ErrorKind::Unsupported`:
+    /// they must be set with `SpellLauncher::format`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::{SpellLauncher, Format};
+    /// let mut checker = SpellLauncher::new()
+    ///                   .launch()
+    ///                   .unwrap();
+    /// checker.set_format(Format::Tex).unwrap();
+    /// let errors = checker.check("\\emph{Hello} world").unwrap();
+    /// assert!(errors.is_empty());
+    /// ```
+    pub fn set_format(&mut self, format: Format) -> Result<()> {
+        let command = protocol::format_command(format, self.backend_info.flavour, self.launch_format)?;
+        if let Some(ref mut launcher) = self.launcher {
+            launcher.set_format(format);
+        }
+        self.retry(|checker| checker.send(&[command]))
+    }
+
+    /// Enables or disables the terse mode
+    ///
+    /// See `SpellLauncher::terse`.
+    pub fn set_terse(&mut self, terse: bool) -> Result<()> {
+        if let Some(ref mut launcher) = self.launcher {
+            launcher.set_terse(terse);
+        }
+        let command: &[u8] = if terse { b"!\n" } else { b"%\n" };
+        self.retry(|checker| {
+            checker.send(&[command])?;
+            checker.terse = terse;
+            Ok(())
+        })
+    }
+
     /// Checks the spelling of a line.
     ///
     /// This method only returns the errors that ispell detects. Since the position returned
@@ -233,99 +570,177 @@ impl SpellChecker {
         Ok(errors)
     }
 
-    
+    /// Checks the spelling of a whole document.
+    ///
+    /// The text is split into lines (ending either with `\n` or `\r\n`) which are checked
+    /// one by one, so, unlike `check`, the returned errors can be located in the
+    /// original document: each `DocumentError` contains its line, its column and
+    /// its offset (in bytes) since the beginning of `text`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    /// let mut checker = SpellLauncher::new().launch().unwrap();
+    /// let text = "This line is correct.\r\nDoes thit one contain any erors?\n";
+    /// let errors = checker.check_document(text).unwrap();
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(&errors[0].error.misspelled, "thit");
+    /// assert_eq!(errors[0].line, 1);
+    /// assert_eq!(errors[0].column.char(), 5);
+    /// let offset = errors[0].offset.byte();
+    /// assert_eq!(&text[offset..offset + 4], "thit");
+    /// ```
+    pub fn check_document(&mut self, text: &str) -> Result<Vec<DocumentError>> {
+        let mut errors = vec!();
+        for (n, (start, line)) in lines(text).enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            for error in self.check(line)? {
+                errors.push(DocumentError::new(error, n, start));
+            }
+        }
+        Ok(errors)
+    }
+
+    /// Checks the spelling of many lines at once
+    ///
+    /// This returns the errors of each line, like `check` would, but is much faster
+    /// on many lines, since the lines are sent to the spawned process without waiting
+    /// for the answer to the previous ones.
+    ///
+    /// If `SpellLauncher::batch_timeout` was set, the whole batch must be checked
+    /// before it expires. If automatic restarts are enabled, the whole batch is
+    /// checked again after a restart.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    /// let mut checker = SpellLauncher::new().launch().unwrap();
+    /// let errors = checker.check_batch(&["Does thit message", "contain any erors?", "No."]).unwrap();
+    /// assert_eq!(errors.len(), 3);
+    /// assert_eq!(&errors[0][0].misspelled, "thit");
+    /// assert_eq!(&errors[1][0].misspelled, "erors");
+    /// assert!(errors[2].is_empty());
+    /// ```
+    pub fn check_batch(&mut self, lines: &[&str]) -> Result<Vec<Vec<IspellError>>> {
+        self.retry(|checker| checker.check_lines(lines).collect())
+    }
+
+    /// Checks the spelling of many lines, lazily
+    ///
+    /// This is similar to `check_batch`, but returns an iterator over the errors of each
+    /// line, so `lines` doesn't need to be in memory. There are no automatic restarts:
+    /// after an error, the iterator stops.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    /// let mut checker = SpellLauncher::new().launch().unwrap();
+    /// let lines = (0..1000).map(|n| format!("Line {} contains a msitake", n));
+    /// for errors in checker.check_lines(lines) {
+    ///     assert_eq!(&errors.unwrap()[0].misspelled, "msitake");
+    /// }
+    /// ```
+    pub fn check_lines<I, S>(&mut self, lines: I) -> CheckLines<'_, I::IntoIter>
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str> {
+        let timeout = self.launcher.as_ref().and_then(|launcher| launcher.get_batch_timeout());
+        CheckLines::new(self, lines.into_iter(), timeout)
+    }
+
+    /// Checks the spelling of a text read from `reader`, lazily
+    ///
+    /// This returns an iterator over the errors of the text, located like in
+    /// `check_document`. The text is read and checked as the iterator advances, so
+    /// only a few lines are in memory at any time. The iterator stops after the
+    /// first error, e.g. if reading fails or the text isn't valid UTF-8.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::io::Cursor;
+    /// use ispell::SpellLauncher;
+    /// let mut checker = SpellLauncher::new().launch().unwrap();
+    /// let text = Cursor::new("Does thit message\ncontain any erors?\n");
+    /// let errors: Vec<_> = checker.check_reader(text)
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(&errors[1].error.misspelled, "erors");
+    /// assert_eq!(errors[1].line, 1);
+    /// ```
+    ///
+    /// To check a file, wrap it in a `BufReader`:
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use ispell::SpellLauncher;
+    /// let mut checker = SpellLauncher::new().launch().unwrap();
+    /// let file = BufReader::new(File::open("corpus.txt").unwrap());
+    /// for error in checker.check_reader(file) {
+    ///     let error = error.unwrap();
+    ///     println!("{}:{}: {}", error.line + 1, error.column, error.error.misspelled);
+    /// }
+    /// ```
+    pub fn check_reader<R: BufRead>(&mut self, reader: R) -> CheckReader<'_, R> {
+        CheckReader::new(self, reader)
+    }
+
+    /// Returns the unit in which the spawned process counts positions
+    pub(crate) fn unit(&self) -> Unit {
+        self.unit
+    }
+
     /// Checks the spelling of a string
     ///
     /// This method returns a vector of all `ispell` answers, even when
-    /// there is no errors. Usually, the `check` method, which only returns
-    /// errors, will be more useful.
+    /// there is no errors (unless the terse mode is enabled). Usually, the
+    /// `check` method, which only returns errors, will be more useful.
     pub fn check_raw(&mut self, text: &str) -> Result<Vec<IspellResult>> {
-        self.write_str(text)?;
-    
-        let mut output = Vec::new();
-
-        if let Ok(s) = self.read_str() {
-            for line in s.lines() {
-                if line.is_empty() {
-                    break;
-                }
-                let first = line.chars().next().unwrap();
-                match first {
-                    '*' => output.push(IspellResult::Ok),
-                    '-' => output.push(IspellResult::Compound),
-                    '+' => {
-                        let words:Vec<_> = line.split_whitespace().collect();
-                        if words.len() != 2 {
-                            return Err(Error::protocol(format!("'root' line ill-formatted: {}", line)));
-                        }
-                        output.push(IspellResult::Root(words[1].to_owned()));
-                    },
-                    '#' => {
-                        let error = get_ispell_error(line, 3)?;
-                        output.push(IspellResult::None(error));
-                    },
-                    '&' | '?' => {
-                        let parts: Vec<_> = line.split(':').collect();
-                        if parts.len() != 2 {
-                            return Err(Error::protocol(format!("unexpected output from ispell: {}", line)));
-                        }
-                        let mut error = get_ispell_error(parts[0], 4)?;
-                        let suggestions: Vec<_> = parts[1].split(",")
-                            .map(|s| s.trim().to_owned())
-                            .collect();
-                        error.suggestions = suggestions;
-                        if first == '&' {
-                            output.push(IspellResult::Miss(error));
-                        } else {
-                            output.push(IspellResult::Guess(error));
-                        }
-                    },
-                    _ => return Err(Error::protocol(format!("unexpected output: {}", line))),
-                }
-            }
-        }
-    
-        Ok(output)
+        self.retry(|checker| {
+            checker.write_str(text)?;
+            let s = checker.read_str()?;
+            protocol::parse_output(&s, text, checker.unit)
+        })
     }
 }
     
                                                   
 impl Drop for SpellChecker {
     fn drop(&mut self) {
-        // We could do this more nicely 
-        self.ispell.kill().unwrap();
+        // We could do this more nicely
+        if self.ispell.kill().is_ok() {
+            let _ = self.ispell.wait();
+        }
     }
 }
 
     
-/// Transforms a string looking like "# unkwnown POS' or '& unknown n POS' to an IspellError
-fn get_ispell_error(input: &str, n: usize) -> Result<IspellError> {
-    let words: Vec<_> = input.split_whitespace().collect();
-    if words.len() != n {
-        return Err(Error::protocol(format!("unexpected result: {}", input)));
-    }
-    let misspelled = words[1].to_owned();
-    let position:usize = words[n - 1].parse()
-        .map_err(|_| Error::protocol(format!("could not parse '{}' as an int", words[2])))?;
-    Ok(IspellError {
-        misspelled: misspelled,
-        position: position - 1, // remove the '^' character we add for escaping
-        suggestions: vec!(),
+/// Splits a document into lines, returning each of them along with the position
+/// where it starts
+///
+/// Contrary to `str::lines`, the last line is returned even if it is empty, but
+/// it doesn't matter here.
+pub(crate) fn lines(text: &str) -> impl Iterator<Item = (Position, &str)> {
+    let mut start = Position::default();
+    text.split('\n')
+        .map(move |line| {
+            let line_start = start;
+            start = start + Position::end_of(line) + Position::end_of("\n");
+            (line_start, line.strip_suffix('\r').unwrap_or(line))
         })
 }
 
 
 #[test]
-fn add_word() {
-    use spell_launcher::SpellLauncher;
-
-    let mut checker = SpellLauncher::new()
-        .launch()
-        .unwrap();
-        
-    checker.add_word("notaword").unwrap();
-    assert!(checker.check("notaword").unwrap().is_empty());
-
-    checker.add_word("stillnotaword2").unwrap();
-    assert_eq!(checker.check("stillnotaword2").unwrap().len(), 1);
+fn lines_offsets() {
+    let lines: Vec<_> = lines("foo\r\nbär\n\nbaz")
+        .map(|(start, line)| (start.byte(), start.char(), line))
+        .collect();
+    assert_eq!(lines, vec!((0, 0, "foo"), (5, 5, "bär"), (10, 9, ""), (11, 10, "baz")));
 }
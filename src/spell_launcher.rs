@@ -2,11 +2,21 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with
 // this file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::process::Stdio;
+use std::time::Duration;
 
-use spell_checker::SpellChecker;
-use error::{Result, Error};
+use crate::spell_checker::SpellChecker;
+use crate::error::{Result, Error};
+use crate::position::Unit;
+use crate::dictionaries::{self, DictionaryInfo};
+use crate::launcher_config::LauncherConfig;
+use crate::transcript::Transcript;
+#[cfg(feature = "tokio")]
+use crate::async_spell_checker::AsyncSpellChecker;
 
 /// Spell Launcher wizard (ah, ah). A builder for `SpellChecker`.
 ///
@@ -26,7 +36,7 @@ use error::{Result, Error};
 ///
 /// * Launches `aspell` with french (France) language:
 /// 
-/// ```
+/// ```no_run
 /// use ispell::SpellLauncher;
 /// let checker = SpellLauncher::new()
 ///               .aspell()
@@ -34,29 +44,106 @@ use error::{Result, Error};
 ///               .launch()
 ///               .unwrap();
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SpellLauncher {
     lang: Option<String>,
+    language: Option<String>,
+    personal: Option<PathBuf>,
     command: Option<String>,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+    env_clear: bool,
+    current_dir: Option<PathBuf>,
+    transcript: Option<Transcript>,
     mode: Mode,
+    auto: Option<Vec<Mode>>,
+    format: Option<Format>,
+    terse: bool,
     timeout: u64,
+    batch_timeout: Option<u64>,
+    max_restarts: u32,
+    backoff: u64,
 }
 
-#[derive(Debug)]
-enum Mode {
+/// A spell checking program that can be run by `SpellLauncher`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Mode {
+    /// `ispell`
     Ispell,
+    /// `aspell`
     Aspell,
+    /// `hunspell`
     Hunspell,
 }
 
+/// The format of the checked text
+///
+/// In a format other than `Plain`, the spell checker skips the markup, e.g. `\begin`
+/// in TeX or `<div>` in HTML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Format {
+    /// Plain text
+    Plain,
+    /// TeX or LaTeX
+    Tex,
+    /// HTML, XML or SGML
+    Html,
+    /// nroff or troff
+    Nroff,
+    /// Markdown (only supported by `aspell`)
+    Markdown,
+}
+
+impl Mode {
+    /// Returns the name of the command that runs this program
+    pub fn command_name(&self) -> &'static str {
+        match *self {
+            Mode::Ispell => "ispell",
+            Mode::Aspell => "aspell",
+            Mode::Hunspell => "hunspell",
+        }
+    }
+
+    /// Unit in which this program reports the position of words
+    fn unit(&self) -> Unit {
+        match *self {
+            Mode::Ispell => Unit::Byte,
+            Mode::Aspell | Mode::Hunspell => Unit::Char,
+        }
+    }
+}
+
+impl Default for SpellLauncher {
+    fn default() -> SpellLauncher {
+        SpellLauncher::new()
+    }
+}
+
 impl SpellLauncher {
     /// Creates a new spell checker with default options
     pub fn new() -> SpellLauncher {
         SpellLauncher {
             lang: None,
+            language: None,
+            personal: None,
             command: None,
+            args: vec!(),
+            envs: vec!(),
+            env_clear: false,
+            current_dir: None,
+            transcript: None,
             mode: Mode::Ispell,
+            auto: None,
+            format: None,
+            terse: false,
             timeout: 1000,
+            batch_timeout: None,
+            max_restarts: 0,
+            backoff: 0,
         }
     }
 
@@ -65,6 +152,7 @@ impl SpellLauncher {
     /// Will run `aspell` as the command if it is not set
     pub fn aspell(&mut self) -> &mut SpellLauncher {
         self.mode = Mode::Aspell;
+        self.auto = None;
         self
     }
 
@@ -73,6 +161,7 @@ impl SpellLauncher {
     /// Will run `hunspell` as the command if it is not set
     pub fn hunspell(&mut self) -> &mut SpellLauncher {
         self.mode = Mode::Hunspell;
+        self.auto = None;
         self
     }
 
@@ -82,10 +171,62 @@ impl SpellLauncher {
     /// (default setting)
     pub fn ispell(&mut self) -> &mut SpellLauncher {
         self.mode = Mode::Ispell;
+        self.auto = None;
+        self
+    }
+
+    /// Detects which program to run when launching
+    ///
+    /// `hunspell`, `aspell` and `ispell` are tried in this order, and the first one
+    /// that is found in `PATH` and can be launched (in particular, that has the
+    /// requested dictionary) is used. Use `auto_preference` to change the order.
+    ///
+    /// The selected program can then be known with `SpellChecker::mode`. If none of them
+    /// works, the error (of kind `ErrorKind::NoBackend`) tells why each one failed.
+    ///
+    /// This has no effect if a custom command is set with `command`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    /// let checker = SpellLauncher::new()
+    ///               .auto()
+    ///               .launch()
+    ///               .unwrap();
+    /// println!("using {:?}", checker.mode().unwrap());
+    /// ```
+    pub fn auto(&mut self) -> &mut SpellLauncher {
+        self.auto_preference(&[Mode::Hunspell, Mode::Aspell, Mode::Ispell])
+    }
+
+    /// Detects which program to run when launching, trying them in the given order
+    ///
+    /// See `auto`.
+    pub fn auto_preference(&mut self, modes: &[Mode]) -> &mut SpellLauncher {
+        self.auto = Some(modes.to_vec());
+        self
+    }
+
+    /// Sets the mode (i.e., the program to run)
+    ///
+    /// This is equivalent to calling `ispell`, `aspell` or `hunspell`.
+    pub fn mode(&mut self, mode: Mode) -> &mut SpellLauncher {
+        self.mode = mode;
+        self.auto = None;
+        self
+    }
+
+    /// Applies the settings of a `LauncherConfig`
+    ///
+    /// This calls the methods corresponding to the fields that are set in `config`.
+    /// It is mostly useful to load the settings from a configuration file, with the
+    /// `serde` feature.
+    pub fn config(&mut self, config: &LauncherConfig) -> &mut SpellLauncher {
+        config.apply(self);
         self
     }
 
-    
     /// Sets the timeout when checking ispell
     ///
     /// If the spawned process takes longer than this timeout to answer to a query,
@@ -97,7 +238,44 @@ impl SpellLauncher {
         self.timeout = timeout;
         self
     }
-    
+
+    /// Sets a timeout for checking a whole batch of lines
+    ///
+    /// This applies to `SpellChecker::check_batch` and `SpellChecker::check_lines`, in
+    /// addition to the usual timeout for each answer (see `timeout`). It is set in
+    /// milliseconds, and there is none by default.
+    pub fn batch_timeout(&mut self, timeout: u64) -> &mut SpellLauncher {
+        self.batch_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables automatic restarts of the spawned process
+    ///
+    /// By default, if the spawned process crashes or times out, the `SpellChecker` becomes
+    /// unusable and you have to launch a new one. With this option, it is restarted (up to
+    /// `max_restarts` times in a row) and the failed operation is tried again; words
+    /// that were added to the session with `add_word` are added again to the new process.
+    ///
+    /// Before each restart, the checker waits `backoff` milliseconds, doubled at each attempt.
+    /// If all attempts fail, the returned error's `restarts` method gives the number of restarts.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    /// let mut checker = SpellLauncher::new()
+    ///               .auto_restart(3, 100)
+    ///               .launch()
+    ///               .unwrap();
+    /// let errors = checker.check("Does thit message contain any erors?").unwrap();
+    /// assert_eq!(checker.restarts(), 0);
+    /// ```
+    pub fn auto_restart(&mut self, max_restarts: u32, backoff: u64) -> &mut SpellLauncher {
+        self.max_restarts = max_restarts;
+        self.backoff = backoff;
+        self
+    }
+
     /// Set the name of the command to run
     ///
     /// By default, it inferred from the mode (which is `ispell` by default).
@@ -105,11 +283,89 @@ impl SpellLauncher {
     /// Unless you want to run a specific (ispell-compatible) command, you shouldn't use this method directly, but rather
     /// use the `aspell` or `hunspell` methods, since this also allow the the library to know which actual program
     /// is runned and to set encoding options accordingly.
+    ///
+    /// Positions reported by a custom command are expected in bytes, like `ispell` does.
     pub fn command<S: Into<String>>(&mut self, command: S) -> &mut SpellLauncher {
         self.command = Some(command.into());
         self
     }
 
+    /// Adds an argument to pass to the spawned program
+    ///
+    /// It is passed after the arguments set by the launcher (`-a`, the dictionary, ...).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    /// let checker = SpellLauncher::new()
+    ///               .aspell()
+    ///               .arg("--sug-mode=ultra")
+    ///               .launch()
+    ///               .unwrap();
+    /// ```
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut SpellLauncher {
+        self.args.push(arg.as_ref().to_owned());
+        self
+    }
+
+    /// Adds several arguments to pass to the spawned program
+    ///
+    /// See `arg`.
+    pub fn args<I, S>(&mut self, args: I) -> &mut SpellLauncher
+        where I: IntoIterator<Item = S>,
+              S: AsRef<OsStr> {
+        for arg in args {
+            self.arg(arg);
+        }
+        self
+    }
+
+    /// Sets an environment variable for the spawned program
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    /// let checker = SpellLauncher::new()
+    ///               .hunspell()
+    ///               .env("DICPATH", "/usr/share/hunspell")
+    ///               .launch()
+    ///               .unwrap();
+    /// ```
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(&mut self, key: K, value: V) -> &mut SpellLauncher {
+        self.envs.push((key.as_ref().to_owned(), value.as_ref().to_owned()));
+        self
+    }
+
+    /// Clears the environment of the spawned program
+    ///
+    /// Only the variables set with `env` (before or after calling this) are passed to
+    /// the program. Note that this includes `PATH`, which is still used to find the
+    /// program, but not by the program itself.
+    pub fn env_clear(&mut self) -> &mut SpellLauncher {
+        self.env_clear = true;
+        self
+    }
+
+    /// Sets the working directory of the spawned program
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut SpellLauncher {
+        self.current_dir = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Records everything exchanged with the spawned process in `transcript`
+    ///
+    /// This is useful to debug protocol issues. If the process is restarted, the new
+    /// process is recorded in the same transcript. This is not supported by
+    /// `launch_async`.
+    ///
+    /// See `Transcript` for an example.
+    pub fn transcript(&mut self, transcript: &Transcript) -> &mut SpellLauncher {
+        self.transcript = Some(transcript.clone());
+        self
+    }
+
     /// Determine the dictionary that should be used.
     ///
     /// Note that `ispell`, `hunspell` and `aspell` have different naming schemes:
@@ -120,7 +376,7 @@ impl SpellLauncher {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```no_run
     /// use ispell::SpellLauncher;
     /// let checker = SpellLauncher::new()
     ///               .aspell()
@@ -130,42 +386,430 @@ impl SpellLauncher {
     /// ```
     pub fn dictionary<S: Into<String>>(&mut self, lang: S) -> &mut SpellLauncher {
         self.lang = Some(lang.into());
+        self.language = None;
+        self
+    }
+
+    /// Determine the language that should be used, with a BCP-47 language tag
+    ///
+    /// Contrary to `dictionary`, this doesn't depend on the naming scheme of the program
+    /// that is launched: a tag like "en-GB", "pt-BR" or "de-CH-1901" is matched against the
+    /// installed dictionaries (see `dictionaries`) when launching. If there is no
+    /// dictionary for this region or variant, a dictionary for the base language is used,
+    /// but never one for another region.
+    ///
+    /// If no dictionary matches, `launch` returns an error of kind `ErrorKind::NoDictionary`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    /// let checker = SpellLauncher::new()
+    ///               .auto()
+    ///               .language("en-US")
+    ///               .launch()
+    ///               .unwrap();
+    /// ```
+    pub fn language<S: Into<String>>(&mut self, tag: S) -> &mut SpellLauncher {
+        self.language = Some(tag.into());
+        self.lang = None;
         self
     }
+
+    /// Sets the format of the text that will be checked
+    ///
+    /// This is passed as `-o` (for plain text), `-t`, `-H` or `-n` to `ispell`
+    /// and `hunspell`, and as `--mode=` to `aspell`. `Format::Markdown` is only
+    /// supported by `aspell`: with other programs, `launch` returns an error of kind
+    /// `ErrorKind::Unsupported`.
+    ///
+    /// The format can also be changed once the spell checker is running, with
+    /// `SpellChecker::set_format`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::{SpellLauncher, Format};
+    /// let mut checker = SpellLauncher::new()
+    ///                   .format(Format::Tex)
+    ///                   .launch()
+    ///                   .unwrap();
+    /// let errors = checker.check("\\begin{document} Hello world").unwrap();
+    /// assert!(errors.is_empty());
+    /// ```
+    pub fn format(&mut self, format: Format) -> &mut SpellLauncher {
+        self.format = Some(format);
+        self
+    }
+
+    /// Enables the terse mode
+    ///
+    /// In this mode, the spawned program doesn't report the words that are correct, which
+    /// is faster when checking large texts. It doesn't change the results of `check`, but
+    /// `check_raw` then only returns the misspelled words.
+    ///
+    /// The terse mode can also be toggled once the spell checker is running, with
+    /// `SpellChecker::set_terse`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    /// let mut checker = SpellLauncher::new()
+    ///                   .terse()
+    ///                   .launch()
+    ///                   .unwrap();
+    /// assert!(checker.check_raw("This is correct").unwrap().is_empty());
+    /// assert_eq!(checker.check("This is not corect").unwrap().len(), 1);
+    /// ```
+    pub fn terse(&mut self) -> &mut SpellLauncher {
+        self.terse = true;
+        self
+    }
+
+    /// Sets the file of the personal dictionary
+    ///
+    /// This is the file where `add_word_to_dictionary` saves words, and whose words
+    /// are accepted when checking. If it isn't set, the program uses its default file,
+    /// which depends on the program and the dictionary (e.g. `~/.hunspell_en_US` or
+    /// `~/.aspell.en.pws`).
+    ///
+    /// This is passed as `-p path` to `ispell` and `hunspell`, and as `--personal=path`
+    /// to `aspell`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    /// let path = std::env::temp_dir().join("ispell-doc-words");
+    /// let mut checker = SpellLauncher::new()
+    ///                   .personal_dictionary(&path)
+    ///                   .launch()
+    ///                   .unwrap();
+    /// checker.add_word_to_dictionary("rustacean").unwrap();
+    /// ```
+    pub fn personal_dictionary<P: AsRef<Path>>(&mut self, path: P) -> &mut SpellLauncher {
+        self.personal = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Returns a copy of this launcher where the language tag (if any) has been
+    /// replaced by the name of the matching dictionary
+    fn resolve_language(&self) -> Result<SpellLauncher> {
+        let mut launcher = self.clone();
+        if let Some(ref tag) = self.language {
+            // If dictionaries can't be listed, we can still guess their name
+            let dicts = dictionaries::list(self.mode, self).unwrap_or_default();
+            launcher.lang = Some(dictionaries::resolve(tag, self.mode, &dicts)?);
+            launcher.language = None;
+        }
+        Ok(launcher)
+    }
     
+    /// Lists the dictionaries that are available for the program that will be launched
+    ///
+    /// * for `aspell`, this runs `aspell dump dicts`;
+    /// * for `hunspell`, this parses the output of `hunspell -D`;
+    /// * for `ispell`, this looks for hash files in the usual directories.
+    ///
+    /// If `auto` was called, the dictionaries of all the programs found in `PATH` are returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ispell::SpellLauncher;
+    /// let dictionaries = SpellLauncher::new()
+    ///                    .auto()
+    ///                    .dictionaries()
+    ///                    .unwrap();
+    /// for dict in dictionaries {
+    ///     println!("{:?}: {} ({:?})", dict.backend, dict.name, dict.language_tag());
+    /// }
+    /// ```
+    pub fn dictionaries(&self) -> Result<Vec<DictionaryInfo>> {
+        match self.candidates() {
+            None => dictionaries::list(self.mode, self),
+            Some(candidates) => {
+                let mut dicts = vec!();
+                for (launcher, found) in candidates {
+                    if found {
+                        dicts.extend(dictionaries::list(launcher.mode, &launcher)?);
+                    }
+                }
+                Ok(dicts)
+            },
+        }
+    }
+
     /// Launch `ispell` (or `aspell` or `hunspell`) and return a `SpellChecker`
     pub fn launch(&self) -> Result<SpellChecker> {
-        let command_name: &str = if let Some(ref command) = self.command {
+        match self.candidates() {
+            None => self.launch_mode(),
+            Some(candidates) => {
+                let mut failures = vec!();
+                for (launcher, found) in candidates {
+                    let result = if found {
+                        launcher.launch_mode()
+                    } else {
+                        Err(Error::new("not found in PATH"))
+                    };
+                    match result {
+                        Ok(checker) => return Ok(checker),
+                        Err(err) => failures.push((launcher.mode, err.to_string())),
+                    }
+                }
+                Err(Error::no_backend(failures))
+            },
+        }
+    }
+
+    /// Launch `ispell` (or `aspell` or `hunspell`) and return an `AsyncSpellChecker`
+    ///
+    /// This method is only available with the `tokio` feature.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ispell::SpellLauncher;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() {
+    ///     let mut checker = SpellLauncher::new()
+    ///         .launch_async()
+    ///         .await
+    ///         .unwrap();
+    ///     let errors = checker.check("Does thit message contain any erors?").await.unwrap();
+    ///     assert_eq!(errors.len(), 2);
+    /// }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn launch_async(&self) -> Result<AsyncSpellChecker> {
+        match self.candidates() {
+            None => self.launch_mode_async().await,
+            Some(candidates) => {
+                let mut failures = vec!();
+                for (launcher, found) in candidates {
+                    let result = if found {
+                        launcher.launch_mode_async().await
+                    } else {
+                        Err(Error::new("not found in PATH"))
+                    };
+                    match result {
+                        Ok(checker) => return Ok(checker),
+                        Err(err) => failures.push((launcher.mode, err.to_string())),
+                    }
+                }
+                Err(Error::no_backend(failures))
+            },
+        }
+    }
+
+    /// Returns the launchers to try if the mode must be detected, along with
+    /// whether their command was found in `PATH`
+    fn candidates(&self) -> Option<Vec<(SpellLauncher, bool)>> {
+        if self.command.is_some() {
+            return None;
+        }
+        self.auto.as_ref().map(|modes| {
+            modes.iter()
+                .map(|&mode| {
+                    let mut launcher = self.clone();
+                    launcher.mode(mode);
+                    (launcher, find_in_path(mode.command_name()).is_some())
+                })
+                .collect()
+        })
+    }
+
+    /// Launch the program corresponding to the current mode
+    fn launch_mode(&self) -> Result<SpellChecker> {
+        let launcher = self.resolve_language()?;
+        let mut command = launcher.build_command()?;
+        let res = command.spawn();
+
+        match res {
+            Ok(child) => {
+                let mut checker = SpellChecker::with_unit(child, self.timeout, self.unit(),
+                                                          self.transcript.clone())?;
+                if self.terse {
+                    checker.set_terse(true)?;
+                }
+                checker.set_launcher(launcher);
+                Ok(checker)
+            },
+            Err(err) => Err(Error::spawn_failed(self.command_name(), err))
+        }
+    }
+
+    /// Launch the program corresponding to the current mode, asynchronously
+    #[cfg(feature = "tokio")]
+    async fn launch_mode_async(&self) -> Result<AsyncSpellChecker> {
+        let mut command = tokio::process::Command::from(self.resolve_language()?.build_command()?);
+        command.kill_on_drop(true);
+        let res = command.spawn();
+
+        match res {
+            Ok(child) => {
+                let mut checker = AsyncSpellChecker::new(child, self.timeout, self.unit(), self.format).await?;
+                if self.terse {
+                    checker.set_terse(true).await?;
+                }
+                Ok(checker)
+            },
+            Err(err) => Err(Error::spawn_failed(self.command_name(), err))
+        }
+    }
+
+    /// Returns the mode of this launcher, unless it runs a custom command
+    pub(crate) fn get_mode(&self) -> Option<Mode> {
+        if self.command.is_none() {
+            Some(self.mode)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the format set with `format`, if any
+    pub(crate) fn get_format(&self) -> Option<Format> {
+        self.format
+    }
+
+    /// Sets the format that will be used if the process is restarted
+    pub(crate) fn set_format(&mut self, format: Format) {
+        self.format = Some(format);
+    }
+
+    /// Sets whether the terse mode will be used if the process is restarted
+    pub(crate) fn set_terse(&mut self, terse: bool) {
+        self.terse = terse;
+    }
+
+    /// Returns the timeout for checking a batch of lines
+    pub(crate) fn get_batch_timeout(&self) -> Option<Duration> {
+        self.batch_timeout.map(Duration::from_millis)
+    }
+
+    /// Returns the maximal number of automatic restarts, and the initial backoff
+    pub(crate) fn restart_policy(&self) -> (u32, Duration) {
+        (self.max_restarts, Duration::from_millis(self.backoff))
+    }
+
+    /// Returns the name of the command to run
+    pub(crate) fn command_name(&self) -> &str {
+        if let Some(ref command) = self.command {
             command
         } else {
-            match self.mode {
-                Mode::Ispell => "ispell",
-                Mode::Aspell => "aspell",
-                Mode::Hunspell => "hunspell",
-            }
-        };
-        let mut command = Command::new(command_name);
+            self.mode.command_name()
+        }
+    }
+
+    /// Returns the unit in which the spawned process will report positions
+    fn unit(&self) -> Unit {
+        // Positions returned by a custom command are assumed to be in bytes, like
+        // ispell (and ispell-mock) report them
+        if self.command.is_none() {
+            self.mode.unit()
+        } else {
+            Unit::Byte
+        }
+    }
+
+    /// Builds the command to spawn
+    fn build_command(&self) -> Result<Command> {
+        let mut command = self.new_command();
         command.arg("-a")
             .stdin(Stdio::piped())
-            .stdout(Stdio::piped());
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
         if let Some(ref lang) = self.lang {
             command.arg("-d")
                 .arg(lang);
         }
+        if let Some(ref path) = self.personal {
+            match self.mode {
+                Mode::Aspell => {
+                    let mut arg = OsString::from("--personal=");
+                    arg.push(path);
+                    command.arg(arg)
+                },
+                Mode::Hunspell | Mode::Ispell => command.arg("-p").arg(path),
+            };
+        }
         // Try to set encoding to utf-8
         if self.command.is_none() { // only do this if it isn't a custom command
             match self.mode {
-                Mode::Hunspell => command.args(&["-i", "utf-8"]),
+                Mode::Hunspell => command.args(["-i", "utf-8"]),
                 Mode::Aspell => command.arg("--encoding=utf-8"),
                 Mode::Ispell => command.arg("-Tutf8"),
             };
         }
+        if let Some(format) = self.format {
+            match (self.mode, format) {
+                (Mode::Aspell, format) => {
+                    command.arg(match format {
+                        Format::Plain => "--mode=none",
+                        Format::Tex => "--mode=tex",
+                        Format::Html => "--mode=html",
+                        Format::Nroff => "--mode=nroff",
+                        Format::Markdown => "--mode=markdown",
+                    });
+                },
+                (Mode::Ispell, Format::Plain) => { command.arg("-o"); },
+                (Mode::Hunspell, Format::Plain) => (),
+                (_, Format::Tex) => { command.arg("-t"); },
+                (_, Format::Html) => { command.arg("-H"); },
+                (_, Format::Nroff) => { command.arg("-n"); },
+                (mode, Format::Markdown) => {
+                    return Err(Error::unsupported(format!("{} doesn't support the Markdown format",
+                                                          mode.command_name())));
+                },
+            }
+        }
+        command.args(&self.args);
+        Ok(command)
+    }
 
-        let res = command.spawn();
-
-        match res {
-            Ok(child) => SpellChecker::new(child, self.timeout),
-            Err(err) => Err(Error::process(format!("could not successfully spawn process '{}': {}", command_name, err)))
+    /// Creates the command that runs the program, with its environment but no arguments
+    pub(crate) fn new_command(&self) -> Command {
+        let mut command = Command::new(self.command_name());
+        if self.env_clear {
+            command.env_clear();
+        }
+        command.envs(self.envs.iter().map(|(key, value)| (key, value)));
+        if let Some(ref dir) = self.current_dir {
+            command.current_dir(dir);
         }
+        command
     }
 }
+
+/// Looks for an executable in the directories of `PATH`
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths)
+        .flat_map(|dir| {
+            let exe = dir.join(name).with_extension(env::consts::EXE_EXTENSION);
+            vec!(dir.join(name), exe)
+        })
+        .find(|path| path.is_file())
+}
+
+
+#[cfg(unix)]
+#[test]
+fn env_clear_keeps_envs() {
+    let command = SpellLauncher::new()
+        .env("BEFORE", "1")
+        .env_clear()
+        .env("AFTER", "2")
+        .new_command();
+    let envs: Vec<_> = command.get_envs().collect();
+    assert_eq!(envs, vec!((OsStr::new("AFTER"), Some(OsStr::new("2"))),
+                          (OsStr::new("BEFORE"), Some(OsStr::new("1")))));
+}
+
+#[test]
+fn find_executables() {
+    assert!(find_in_path("sh").is_some());
+    assert!(find_in_path("not-a-spell-checker").is_none());
+}
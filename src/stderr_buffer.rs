@@ -0,0 +1,129 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader};
+use std::process::ChildStderr;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Maximal number of lines kept in a `StderrBuffer`
+const MAX_LINES: usize = 100;
+
+/// Keeps the last lines a spawned process wrote to its stderr
+///
+/// It is shared between the spell checker and the thread (or task) that reads stderr.
+#[derive(Clone, Default)]
+pub(crate) struct StderrBuffer {
+    inner: Arc<(Mutex<State>, Condvar)>,
+}
+
+#[derive(Default)]
+struct State {
+    lines: VecDeque<String>,
+    closed: bool,
+}
+
+impl StderrBuffer {
+    /// Spawns a thread that reads `stderr` and returns the buffer it fills
+    pub fn spawn(stderr: ChildStderr) -> StderrBuffer {
+        let buffer = StderrBuffer::default();
+        let writer = buffer.clone();
+        thread::spawn(move || {
+            let mut stderr = BufReader::new(stderr);
+            let mut line = vec!();
+            loop {
+                line.clear();
+                match stderr.read_until(b'\n', &mut line) {
+                    Ok(0) => break,
+                    Ok(_) => writer.push_bytes(&line),
+                    Err(ref err) if err.kind() == io::ErrorKind::Interrupted => (),
+                    Err(_) => break,
+                }
+            }
+            writer.close();
+        });
+        buffer
+    }
+
+    /// Adds a line read as bytes, which don't have to be valid UTF-8
+    ///
+    /// Stopping at an invalid line would leave stderr undrained, and the process
+    /// would block once the pipe is full.
+    pub fn push_bytes(&self, line: &[u8]) {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        self.push(String::from_utf8_lossy(line).into_owned());
+    }
+
+    /// Adds a line, dropping the oldest one if the buffer is full
+    pub fn push(&self, line: String) {
+        let mut state = self.inner.0.lock().unwrap();
+        if state.lines.len() == MAX_LINES {
+            state.lines.pop_front();
+        }
+        state.lines.push_back(line);
+    }
+
+    /// Marks stderr as closed
+    pub fn close(&self) {
+        self.inner.0.lock().unwrap().closed = true;
+        self.inner.1.notify_all();
+    }
+
+    /// Returns true if stderr is closed
+    #[cfg(feature = "tokio")]
+    pub fn is_closed(&self) -> bool {
+        self.inner.0.lock().unwrap().closed
+    }
+
+    /// Waits until stderr is closed, or the timeout expires
+    ///
+    /// This is useful when the process just exited, since the last lines it wrote
+    /// may not have been read yet.
+    pub fn wait_closed(&self, timeout: Duration) {
+        let state = self.inner.0.lock().unwrap();
+        let _ = self.inner.1.wait_timeout_while(state, timeout, |state| !state.closed);
+    }
+
+    /// Returns the lines in the buffer
+    pub fn lines(&self) -> Vec<String> {
+        self.inner.0.lock().unwrap().lines.iter().cloned().collect()
+    }
+
+    /// Returns the lines in the buffer, joined by newlines
+    pub fn text(&self) -> String {
+        self.lines().join("\n")
+    }
+}
+
+
+#[test]
+fn bounded_buffer() {
+    let buffer = StderrBuffer::default();
+    for i in 0..MAX_LINES + 2 {
+        buffer.push(format!("line {}", i));
+    }
+    let lines = buffer.lines();
+    assert_eq!(lines.len(), MAX_LINES);
+    assert_eq!(&lines[0], "line 2");
+    buffer.close();
+    buffer.wait_closed(Duration::from_secs(10));
+}
+
+#[test]
+fn invalid_utf8() {
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("sh")
+        .args(["-c", "printf 'caf\\351\\r\\nstill read\\n' >&2"])
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let buffer = StderrBuffer::spawn(child.stderr.take().unwrap());
+    child.wait().unwrap();
+    buffer.wait_closed(Duration::from_secs(10));
+    assert_eq!(buffer.lines(), vec!("caf\u{FFFD}", "still read"));
+}
@@ -0,0 +1,241 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, Error};
+
+/// Whether some data was sent to the spawned process or received from it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Written to the stdin of the process
+    Sent,
+    /// An answer read from the stdout of the process
+    Received,
+}
+
+/// Some data exchanged with the spawned process
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptEntry {
+    /// Time since the transcript was created
+    pub time: Duration,
+    /// Whether the data was sent or received
+    pub direction: Direction,
+    /// The exact data, including line breaks
+    pub data: String,
+}
+
+/// A record of everything exchanged with the spawned process
+///
+/// When it is given to `SpellLauncher::transcript`, every line written to the spawned
+/// process and every answer it gives (including its version banner) is recorded, with
+/// the time since the transcript was created. This is useful to debug protocol issues.
+/// Clones of a transcript share the same entries.
+///
+/// A transcript can be saved to a text file, with one entry by line: the time in
+/// seconds, `>` for sent data or `<` for received data, and the data, where line breaks
+/// and backslashes are escaped (`\n`, `\r`, `\t` and `\\`). Lines starting with `#`
+/// are comments.
+///
+/// A saved transcript can then be replayed, i.e. answered as if it was the spawned
+/// process (see `replay`), which allows to write regression tests for the
+/// output of each program and version. With the `mock` feature, `ispell-mock --replay
+/// FILE` does this.
+///
+/// # Example
+///
+/// ```no_run
+/// use ispell::{SpellLauncher, Transcript, Direction};
+/// let transcript = Transcript::new();
+/// let mut checker = SpellLauncher::new()
+///                   .transcript(&transcript)
+///                   .launch()
+///                   .unwrap();
+/// checker.check("Does thit message contain any erors?").unwrap();
+/// let entries = transcript.entries();
+/// assert_eq!(entries[0].direction, Direction::Received); // the banner
+/// assert_eq!(&entries[1].data, "^Does thit message contain any erors?\n");
+/// println!("{}", transcript);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    entries: Arc<Mutex<Vec<TranscriptEntry>>>,
+    start: Instant,
+}
+
+impl Default for Transcript {
+    fn default() -> Transcript {
+        Transcript::new()
+    }
+}
+
+impl Transcript {
+    /// Creates a new, empty transcript
+    pub fn new() -> Transcript {
+        Transcript {
+            entries: Arc::new(Mutex::new(vec!())),
+            start: Instant::now(),
+        }
+    }
+
+    /// Adds an entry
+    pub(crate) fn record(&self, direction: Direction, data: &[u8]) {
+        let entry = TranscriptEntry {
+            time: self.start.elapsed(),
+            direction,
+            data: String::from_utf8_lossy(data).into_owned(),
+        };
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    /// Returns the entries recorded so far
+    pub fn entries(&self) -> Vec<TranscriptEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// Parses a transcript saved as text
+    pub fn parse(text: &str) -> Result<Transcript> {
+        let transcript = Transcript::new();
+        let mut entries = vec!();
+        for line in text.lines() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || Error::new(format!("invalid transcript line: {}", line));
+            let mut parts = line.splitn(3, ' ');
+            let time = parts.next()
+                .and_then(|time| time.parse::<f64>().ok())
+                // `Duration::from_secs_f64` panics on values it can't represent
+                .filter(|time| time.is_finite() && *time >= 0.0 && *time < u64::MAX as f64)
+                .ok_or_else(invalid)?;
+            let direction = match parts.next() {
+                Some(">") => Direction::Sent,
+                Some("<") => Direction::Received,
+                _ => return Err(invalid()),
+            };
+            let data = unescape(parts.next().unwrap_or("")).ok_or_else(invalid)?;
+            entries.push(TranscriptEntry {
+                time: Duration::from_secs_f64(time),
+                direction,
+                data,
+            });
+        }
+        *transcript.entries.lock().unwrap() = entries;
+        Ok(transcript)
+    }
+
+    /// Reads a transcript from a file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Transcript> {
+        Transcript::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Saves this transcript to a file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Ok(fs::write(path, self.to_string())?)
+    }
+
+    /// Answers what is read from `input` with the answers of this transcript
+    ///
+    /// This acts as the spawned process did: it writes the data that was received, and
+    /// reads a line from `input` for each line that was sent. It fails if a line read
+    /// from `input` differs from the one in the transcript, or if `input` is closed
+    /// before the end of the transcript.
+    pub fn replay<R: BufRead, W: Write>(&self, mut input: R, mut output: W) -> Result<()> {
+        for entry in self.entries() {
+            match entry.direction {
+                Direction::Received => {
+                    output.write_all(entry.data.as_bytes())?;
+                    output.flush()?;
+                },
+                Direction::Sent => {
+                    for expected in entry.data.split_terminator('\n') {
+                        let mut line = String::new();
+                        if input.read_line(&mut line)? == 0 {
+                            return Err(Error::new(format!("input closed while expecting {:?}", expected)));
+                        }
+                        if line.trim_end_matches('\n') != expected {
+                            return Err(Error::new(format!("expected {:?}, read {:?}", expected, line)));
+                        }
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in self.entries.lock().unwrap().iter() {
+            let direction = match entry.direction {
+                Direction::Sent => '>',
+                Direction::Received => '<',
+            };
+            writeln!(f, "{:.6} {} {}", entry.time.as_secs_f64(), direction, escape(&entry.data))?;
+        }
+        Ok(())
+    }
+}
+
+/// Escapes line breaks, tabs and backslashes
+fn escape(data: &str) -> String {
+    let mut escaped = String::with_capacity(data.len());
+    for c in data.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\\' => escaped.push_str("\\\\"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Reverses `escape`
+fn unescape(data: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(data.len());
+    let mut chars = data.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '\\' => '\\',
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
+
+
+#[test]
+fn save_and_replay() {
+    let transcript = Transcript::new();
+    transcript.record(Direction::Received, b"@(#) International Ispell Version 3.2.06\n");
+    transcript.record(Direction::Sent, b"!\n");
+    transcript.record(Direction::Sent, b"^tab\there \\o/\n");
+    transcript.record(Direction::Received, b"& thit 1 5: this\n\n");
+
+    let parsed = Transcript::parse(&format!("# comment\n{}", transcript)).unwrap();
+    assert_eq!(parsed.entries().len(), 4);
+    assert_eq!(&parsed.entries()[2].data, "^tab\there \\o/\n");
+
+    let mut output = vec!();
+    parsed.replay(&b"!\n^tab\there \\o/\n"[..], &mut output).unwrap();
+    assert_eq!(output, b"@(#) International Ispell Version 3.2.06\n& thit 1 5: this\n\n");
+    assert!(parsed.replay(&b"!\n^something else\n"[..], &mut vec!()).is_err());
+    assert!(Transcript::parse("0.1 ? data").is_err());
+    assert!(Transcript::parse("inf > data").is_err());
+    assert!(Transcript::parse("1e30 > data").is_err());
+}
@@ -0,0 +1,177 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with
+// this file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests of `SpellLauncher` and `SpellChecker` against `ispell-mock`, so they don't
+//! depend on the installed spell checkers
+
+#![cfg(feature = "mock")]
+
+use ispell::{SpellLauncher, SpellCheckerPool, ErrorKind, Flavour, Transcript};
+
+const MOCK: &str = env!("CARGO_BIN_EXE_ispell-mock");
+
+fn launcher() -> SpellLauncher {
+    let mut launcher = SpellLauncher::new();
+    launcher.command(MOCK)
+        .args(["--misspelled", "thit: this, that", "--misspelled", "rustacean"]);
+    launcher
+}
+
+#[test]
+fn check() {
+    let mut checker = launcher().launch().unwrap();
+    assert_eq!(checker.backend_info().flavour, Flavour::Unknown);
+    let errors = checker.check("Café: does thit contain a rustacean?").unwrap();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(&errors[0].misspelled, "thit");
+    assert_eq!(errors[0].position.char(), 11);
+    assert_eq!(errors[0].suggestions, vec!("this", "that"));
+    assert!(errors[1].suggestions.is_empty());
+
+    checker.add_word("rustacean").unwrap();
+    assert_eq!(checker.check("a rustacean").unwrap().len(), 0);
+}
+
+#[test]
+fn add_word() {
+    let mut checker = launcher()
+        .args(["--misspelled", "notaword", "--misspelled", "stillnotaword2"])
+        .launch()
+        .unwrap();
+
+    checker.add_word("notaword").unwrap();
+    assert!(checker.check("notaword").unwrap().is_empty());
+
+    checker.add_word("stillnotaword2").unwrap();
+    assert_eq!(checker.check("stillnotaword2").unwrap().len(), 1);
+}
+
+#[test]
+fn failures() {
+    let mut checker = launcher().arg("--crash-after").arg("1").launch().unwrap();
+    checker.check("thit").unwrap();
+    let err = checker.check("thit").unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::ProcessExited { .. }), "{}", err);
+    assert!(err.stderr().contains("simulated crash"));
+
+    let mut checker = launcher().args(["--malformed-after", "0"]).launch().unwrap();
+    let err = checker.check("thit").unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Protocol { .. }), "{}", err);
+    // The other answers in flight are read, so the checker can still be used
+    assert!(checker.check_lines(&["a", "b", "c"]).next().unwrap().is_err());
+    assert!(checker.is_alive());
+
+    let mut checker = launcher().args(["--latency", "500"]).timeout(100).launch().unwrap();
+    assert_eq!(checker.check("thit").unwrap_err().kind(), &ErrorKind::Timeout);
+}
+
+#[test]
+fn restart() {
+    // Adding a word to the dictionary needs two answers, so the crash happens during `check`
+    let mut checker = launcher().args(["--crash-after", "2"]).auto_restart(1, 0).launch().unwrap();
+    checker.add_word("rustacean").unwrap();
+    assert!(checker.add_words_to_dictionary(["thit"]).unwrap().is_empty());
+    assert!(checker.check("rustacean").unwrap().is_empty());
+    assert!(checker.check("thit rustacean").unwrap().is_empty());
+    assert_eq!(checker.restarts(), 1);
+}
+
+#[test]
+fn replace_dead_checkers() {
+    let mut launcher = launcher();
+    launcher.args(["--crash-after", "1"]);
+    let pool = SpellCheckerPool::new(&launcher, 1).unwrap();
+    pool.add_word("rustacean").unwrap();
+    {
+        let mut checker = pool.get().unwrap();
+        assert!(checker.check("rustacean").unwrap().is_empty());
+        assert!(pool.try_get().unwrap().is_none());
+        assert!(checker.check("rustacean").is_err());
+    }
+    let mut checker = pool.get().unwrap();
+    assert!(checker.is_alive());
+    assert!(checker.check("rustacean").unwrap().is_empty());
+}
+
+#[test]
+fn check_more_lines_than_in_flight() {
+    let mut checker = launcher().args(["--misspelled", "msitake"]).launch().unwrap();
+    let lines: Vec<_> = (0..200)
+        .map(|n| if n % 2 == 0 { format!("line {}", n) } else { format!("line {} has a msitake", n) })
+        .collect();
+    let results: Vec<_> = checker.check_lines(&lines).collect::<Result<_, _>>().unwrap();
+    assert_eq!(results.len(), lines.len());
+    for (n, errors) in results.iter().enumerate() {
+        assert_eq!(errors.len(), n % 2);
+    }
+
+    // Stopping early must not mess up with the next answers
+    assert!(checker.check_lines(&lines).next().unwrap().unwrap().is_empty());
+    assert_eq!(checker.check("a msitake").unwrap().len(), 1);
+}
+
+#[test]
+fn check_reader_io_error() {
+    let text: &[u8] = b"first msitake\n\nthird lin\xffe\nnot read";
+    let mut checker = launcher().args(["--misspelled", "msitake"]).launch().unwrap();
+    let results: Vec<_> = checker.check_reader(text).collect();
+    assert_eq!(results.len(), 2);
+    let error = results[0].as_ref().unwrap();
+    assert_eq!((error.line, error.column.char(), error.offset.byte()), (0, 6, 6));
+    assert!(results[1].is_err());
+    assert_eq!(checker.check("a msitake").unwrap().len(), 1);
+}
+
+#[test]
+fn replay() {
+    let transcript = Transcript::new();
+    let mut checker = launcher().transcript(&transcript).launch().unwrap();
+    checker.add_word("rustacean").unwrap();
+    let errors = checker.check_batch(&["Does thit work?", "A rustacean"]).unwrap();
+    drop(checker);
+
+    let path = std::env::temp_dir().join(format!("ispell-transcript-{}", std::process::id()));
+    transcript.save(&path).unwrap();
+    let mut checker = SpellLauncher::new()
+        .command(MOCK)
+        .args(["--replay".as_ref(), path.as_os_str()])
+        .launch()
+        .unwrap();
+    checker.add_word("rustacean").unwrap();
+    assert_eq!(checker.check_batch(&["Does thit work?", "A rustacean"]).unwrap(), errors);
+    // This wasn't in the transcript
+    assert!(checker.check("thit").is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn personal_dictionary() {
+    let path = std::env::temp_dir().join(format!("ispell-personal-{}", std::process::id()));
+    let mut checker = launcher().personal_dictionary(&path).launch().unwrap();
+    let rejected = checker.add_words_to_dictionary(["rustacean", "foo-bar", "two words", "thit"]).unwrap();
+    let rejected: Vec<_> = rejected.iter().map(|rejected| rejected.word.as_str()).collect();
+    assert_eq!(rejected, vec!("foo-bar", "two words"));
+    assert!(checker.check("thit rustacean").unwrap().is_empty());
+    // Nothing is written until the dictionary is saved
+    assert!(!path.exists());
+
+    checker.save_dictionary().unwrap();
+    checker.check("sync").unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "rustacean\nthit\n");
+
+    let err = checker.add_word_to_dictionary("foo-bar").unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::InvalidWord);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn personal_dictionary_terse() {
+    let mut checker = launcher().terse().launch().unwrap();
+    let rejected = checker.add_words_to_dictionary(["foo-bar", "rustacean"]).unwrap();
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(&rejected[0].word, "foo-bar");
+    // The terse mode is restored
+    assert!(checker.check_raw("a rustacean").unwrap().is_empty());
+    assert_eq!(checker.check_raw("thit").unwrap().len(), 1);
+}